@@ -1,6 +1,6 @@
-use rand::{thread_rng, Rng};
+use rand::{Rng, SeedableRng, ChaChaRng};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
@@ -10,15 +10,555 @@ impl Point {
     pub fn sq_dist(&self, other: &Point) -> f64 {
         ((self.x - other.x) * (self.x - other.x)) + ((self.y - other.y) * (self.y - other.y))
     }
+
+    pub fn steer(&self, target: &Point, step: f64) -> Point {
+        let sq_dist = self.sq_dist(target);
+        if sq_dist <= step * step {
+            target.clone()
+        } else {
+            let ratio = step / sq_dist.sqrt();
+            Point {
+                x: self.x + (target.x - self.x) * ratio,
+                y: self.y + (target.y - self.y) * ratio,
+            }
+        }
+    }
+}
+
+// robot position with heading in radians (measured from the x axis towards the y axis)
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Pose {
+    pub point: Point,
+    pub heading: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Direction {
+    Forward,
+    Reverse,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CircleArea {
     pub center: Point,
     pub radius: f64,
 }
 
-#[derive(Clone, Debug)]
+impl CircleArea {
+    pub fn intersects_segment(&self, src: &Point, dst: &Point, clearance: f64) -> bool {
+        let radius = self.radius + clearance;
+        let seg_v = Point { x: dst.x - src.x, y: dst.y - src.y, };
+        let seg_v_len = (seg_v.x * seg_v.x + seg_v.y * seg_v.y).sqrt();
+        if seg_v_len <= 0. {
+            return src.sq_dist(&self.center) < radius * radius;
+        }
+        let closest_point = {
+            let pt_v = Point { x: self.center.x - src.x, y: self.center.y - src.y, };
+            let seg_v_unit = Point { x: seg_v.x / seg_v_len, y: seg_v.y / seg_v_len, };
+            let proj = pt_v.x * seg_v_unit.x + pt_v.y * seg_v_unit.y;
+            if proj <= 0. {
+                src.clone()
+            } else if proj >= seg_v_len {
+                dst.clone()
+            } else {
+                let proj_v = Point { x: seg_v_unit.x * proj, y: seg_v_unit.y * proj, };
+                Point { x: proj_v.x + src.x, y: proj_v.y + src.y, }
+            }
+        };
+        closest_point.sq_dist(&self.center) < radius * radius
+    }
+}
+
+// simple (not self intersecting) polygon, either convex or concave, vertices may go in any direction
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PolygonArea {
+    pub vertices: Vec<Point>,
+}
+
+impl PolygonArea {
+    pub fn edges<'a>(&'a self) -> impl Iterator<Item = (&'a Point, &'a Point)> + 'a {
+        let count = self.vertices.len();
+        (0 .. count).map(move |i| (&self.vertices[i], &self.vertices[(i + 1) % count]))
+    }
+
+    // even-odd rule ray casting
+    pub fn contains(&self, point: &Point) -> bool {
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            if (a.y > point.y) != (b.y > point.y) {
+                let cross_x = a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y);
+                if point.x < cross_x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+
+    pub fn intersects_segment(&self, src: &Point, dst: &Point, clearance: f64) -> bool {
+        if self.vertices.len() < 3 {
+            return false;
+        }
+        if self.contains(src) || self.contains(dst) {
+            return true;
+        }
+        self.edges().any(|(a, b)| {
+            segments_intersect(src, dst, a, b) ||
+                (clearance > 0. && segments_sq_dist(src, dst, a, b) < clearance * clearance)
+        })
+    }
+
+    // whether the polygon itself holds the whole axis aligned square: all the corners are inside and no edge
+    // crosses the square sides
+    fn holds_square(&self, center: &Point, half_side: f64) -> bool {
+        if self.vertices.len() < 3 {
+            return false;
+        }
+        let corners = square_corners(center, half_side);
+        corners.iter().all(|corner| self.contains(corner)) &&
+            !self.edges().any(|(a, b)| (0 .. 4).any(|i| segments_intersect(a, b, &corners[i], &corners[(i + 1) % 4])))
+    }
+
+    // ear clipping, used to render concave polygons with a triangles only backend
+    pub fn triangulate(&self) -> Vec<[Point; 3]> {
+        let mut triangles = Vec::new();
+        if self.vertices.len() < 3 {
+            return triangles;
+        }
+        let mut indices: Vec<usize> = (0 .. self.vertices.len()).collect();
+        let winding = if self.signed_area() < 0. { -1. } else { 1. };
+        while indices.len() > 3 {
+            let count = indices.len();
+            let ear = (0 .. count).find(|&i| {
+                let (ia, ib, ic) = (indices[(i + count - 1) % count], indices[i], indices[(i + 1) % count]);
+                let (a, b, c) = (&self.vertices[ia], &self.vertices[ib], &self.vertices[ic]);
+                if orientation(a, b, c) * winding <= 0. {
+                    return false;
+                }
+                indices.iter()
+                    .filter(|&&j| j != ia && j != ib && j != ic)
+                    .all(|&j| !triangle_contains(a, b, c, &self.vertices[j]))
+            });
+            // degenerate input (collinear or self intersecting): just cut off the next vertex
+            let ear = ear.unwrap_or(0);
+            triangles.push([
+                self.vertices[indices[(ear + count - 1) % count]],
+                self.vertices[indices[ear]],
+                self.vertices[indices[(ear + 1) % count]],
+            ]);
+            indices.remove(ear);
+        }
+        triangles.push([self.vertices[indices[0]], self.vertices[indices[1]], self.vertices[indices[2]]]);
+        triangles
+    }
+
+    fn signed_area(&self) -> f64 {
+        self.edges().map(|(a, b)| a.x * b.y - b.x * a.y).sum::<f64>() / 2.
+    }
+}
+
+// box of `width` x `height` centered at `center`, rotated by `angle` radians (zero for an axis-aligned one)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RectangleArea {
+    pub center: Point,
+    pub width: f64,
+    pub height: f64,
+    #[serde(default)]
+    pub angle: f64,
+}
+
+impl RectangleArea {
+    pub fn from_corners(a: &Point, b: &Point) -> RectangleArea {
+        RectangleArea {
+            center: Point { x: (a.x + b.x) / 2., y: (a.y + b.y) / 2., },
+            width: (b.x - a.x).abs(),
+            height: (b.y - a.y).abs(),
+            angle: 0.,
+        }
+    }
+
+    // box with one side going from `a` to `b` and the opposite one passing through `depth`
+    pub fn from_side(a: &Point, b: &Point, depth: &Point) -> RectangleArea {
+        let width = a.sq_dist(b).sqrt();
+        let angle = (b.y - a.y).atan2(b.x - a.x);
+        let (sin, cos) = angle.sin_cos();
+        let offset = (depth.x - a.x) * -sin + (depth.y - a.y) * cos;
+        RectangleArea {
+            center: Point {
+                x: (a.x + b.x) / 2. - sin * offset / 2.,
+                y: (a.y + b.y) / 2. + cos * offset / 2.,
+            },
+            width,
+            height: offset.abs(),
+            angle,
+        }
+    }
+
+    fn to_local(&self, point: &Point) -> Point {
+        let (sin, cos) = self.angle.sin_cos();
+        let (dx, dy) = (point.x - self.center.x, point.y - self.center.y);
+        Point { x: dx * cos + dy * sin, y: dy * cos - dx * sin, }
+    }
+
+    pub fn intersects_segment(&self, src: &Point, dst: &Point, clearance: f64) -> bool {
+        let src = self.to_local(src);
+        let dst = self.to_local(dst);
+        if self.clips_local_segment(&src, &dst) {
+            return true;
+        }
+        if clearance <= 0. {
+            return false;
+        }
+        let (half_w, half_h) = (self.width / 2., self.height / 2.);
+        let corners = [
+            Point { x: -half_w, y: -half_h, },
+            Point { x: half_w, y: -half_h, },
+            Point { x: half_w, y: half_h, },
+            Point { x: -half_w, y: half_h, },
+        ];
+        (0 .. 4).any(|i| segments_sq_dist(&src, &dst, &corners[i], &corners[(i + 1) % 4]) < clearance * clearance)
+    }
+
+    // Liang-Barsky clipping of the segment in the box own coordinates
+    fn clips_local_segment(&self, src: &Point, dst: &Point) -> bool {
+        let (half_w, half_h) = (self.width / 2., self.height / 2.);
+        let (dx, dy) = (dst.x - src.x, dst.y - src.y);
+        let (mut t_min, mut t_max) = (0., 1.);
+        let clips = [
+            (-dx, src.x + half_w),
+            (dx, half_w - src.x),
+            (-dy, src.y + half_h),
+            (dy, half_h - src.y),
+        ];
+        for &(p, q) in clips.iter() {
+            if p == 0. {
+                if q <= 0. {
+                    return false;
+                }
+            } else {
+                let t = q / p;
+                if p < 0. {
+                    if t > t_max {
+                        return false;
+                    } else if t > t_min {
+                        t_min = t;
+                    }
+                } else {
+                    if t < t_min {
+                        return false;
+                    } else if t < t_max {
+                        t_max = t;
+                    }
+                }
+            }
+        }
+        t_min < t_max
+    }
+}
+
+// bitmap of occupied cells stretched over `area` (`[min_x, min_y, max_x, max_y]` like `FieldConfig::field_area`)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "OccupancyRows", into = "OccupancyRows")]
+pub struct OccupancyGrid {
+    pub area: (f64, f64, f64, f64),
+    cols: usize,
+    rows: usize,
+    cells: Vec<bool>,
+}
+
+impl OccupancyGrid {
+    pub fn new(area: (f64, f64, f64, f64), cols: usize, rows: usize, cells: Vec<bool>) -> OccupancyGrid {
+        assert_eq!(cells.len(), cols * rows);
+        OccupancyGrid { area, cols, rows, cells, }
+    }
+
+    fn cell_size(&self) -> (f64, f64) {
+        let (min_x, min_y, max_x, max_y) = self.area;
+        ((max_x - min_x) / self.cols as f64, (max_y - min_y) / self.rows as f64)
+    }
+
+    fn is_occupied(&self, col: isize, row: isize) -> bool {
+        if col < 0 || row < 0 || col as usize >= self.cols || row as usize >= self.rows {
+            false
+        } else {
+            self.cells[row as usize * self.cols + col as usize]
+        }
+    }
+
+    pub fn intersects_segment(&self, src: &Point, dst: &Point, clearance: f64) -> bool {
+        if self.cells.is_empty() {
+            false
+        } else if clearance <= 0. {
+            self.walk_segment(src, dst)
+        } else {
+            self.scan_segment(src, dst, clearance)
+        }
+    }
+
+    // checks the occupied cells within the clearance of every cell the segment passes: the whole neighbourhood of the
+    // first one, then only the strip each step along the segment brings into reach
+    fn scan_segment(&self, src: &Point, dst: &Point, clearance: f64) -> bool {
+        let (cell_w, cell_h) = self.cell_size();
+        let (reach_x, reach_y) = ((clearance / cell_w).ceil() as isize, (clearance / cell_h).ceil() as isize);
+        let blocked = |(col_a, col_b): (isize, isize), (row_a, row_b): (isize, isize)| {
+            (row_a ..= row_b).any(|row| (col_a ..= col_b).any(|col| {
+                if !self.is_occupied(col, row) {
+                    return false;
+                }
+                let cell = RectangleArea {
+                    center: Point {
+                        x: self.area.0 + (col as f64 + 0.5) * cell_w,
+                        y: self.area.1 + (row as f64 + 0.5) * cell_h,
+                    },
+                    width: cell_w,
+                    height: cell_h,
+                    angle: 0.,
+                };
+                cell.intersects_segment(src, dst, clearance)
+            }))
+        };
+        self.walk_cells(src, dst, |col, row, step_col, step_row| {
+            if step_col == 0 && step_row == 0 {
+                blocked((col - reach_x, col + reach_x), (row - reach_y, row + reach_y))
+            } else {
+                let (strip_col, strip_row) = (col + step_col * reach_x, row + step_row * reach_y);
+                (step_col != 0 && blocked((strip_col, strip_col), (row - reach_y, row + reach_y))) ||
+                    (step_row != 0 && blocked((col - reach_x, col + reach_x), (strip_row, strip_row)))
+            }
+        })
+    }
+
+    fn walk_segment(&self, src: &Point, dst: &Point) -> bool {
+        self.walk_cells(src, dst, |col, row, _, _| self.is_occupied(col, row))
+    }
+
+    // walks all the cells the segment passes (Amanatides-Woo traversal) until `visit` returns true, which is given
+    // the cell along with the step leading to it: none for the first cell, a diagonal one where the segment goes
+    // exactly through a corner and so passes neither of the other two cells sharing it
+    fn walk_cells<F>(&self, src: &Point, dst: &Point, mut visit: F) -> bool
+        where F: FnMut(isize, isize, isize, isize) -> bool
+    {
+        let (cell_w, cell_h) = self.cell_size();
+        let (ax, ay) = ((src.x - self.area.0) / cell_w, (src.y - self.area.1) / cell_h);
+        let (bx, by) = ((dst.x - self.area.0) / cell_w, (dst.y - self.area.1) / cell_h);
+        let (mut col, mut row) = (ax.floor() as isize, ay.floor() as isize);
+        let (end_col, end_row) = (bx.floor() as isize, by.floor() as isize);
+        let axis = |a: f64, b: f64| {
+            let d = b - a;
+            if d > 0. {
+                (1, (a.floor() + 1. - a) / d, 1. / d)
+            } else if d < 0. {
+                (-1, (a - a.floor()) / -d, 1. / -d)
+            } else {
+                (0, ::std::f64::INFINITY, ::std::f64::INFINITY)
+            }
+        };
+        let (step_col, mut t_max_x, t_delta_x) = axis(ax, bx);
+        let (step_row, mut t_max_y, t_delta_y) = axis(ay, by);
+        if visit(col, row, 0, 0) {
+            return true;
+        }
+        loop {
+            if col == end_col && row == end_row {
+                return false;
+            }
+            let t = t_max_x.min(t_max_y);
+            if t > 1. {
+                return false;
+            }
+            let (mut moved_col, mut moved_row) = (0, 0);
+            if t_max_x <= t {
+                col += step_col;
+                t_max_x += t_delta_x;
+                moved_col = step_col;
+            }
+            if t_max_y <= t {
+                row += step_row;
+                t_max_y += t_delta_y;
+                moved_row = step_row;
+            }
+            if visit(col, row, moved_col, moved_row) {
+                return true;
+            }
+        }
+    }
+
+    // whether every cell the axis aligned square overlaps is occupied
+    fn holds_square(&self, center: &Point, half_side: f64) -> bool {
+        if self.cells.is_empty() {
+            return false;
+        }
+        let (cell_w, cell_h) = self.cell_size();
+        let col_a = ((center.x - half_side - self.area.0) / cell_w).floor() as isize;
+        let col_b = ((center.x + half_side - self.area.0) / cell_w).floor() as isize;
+        let row_a = ((center.y - half_side - self.area.1) / cell_h).floor() as isize;
+        let row_b = ((center.y + half_side - self.area.1) / cell_h).floor() as isize;
+        (row_a ..= row_b).all(|row| (col_a ..= col_b).all(|col| self.is_occupied(col, row)))
+    }
+
+    // occupied cells merged into horizontal runs, each one is `[x, y, width, height]`
+    pub fn occupied_runs(&self) -> Vec<[f64; 4]> {
+        let (cell_w, cell_h) = self.cell_size();
+        let mut runs = Vec::new();
+        for row in 0 .. self.rows {
+            let mut col = 0;
+            while col < self.cols {
+                if self.cells[row * self.cols + col] {
+                    let start = col;
+                    while col < self.cols && self.cells[row * self.cols + col] {
+                        col += 1;
+                    }
+                    runs.push([
+                        self.area.0 + start as f64 * cell_w,
+                        self.area.1 + row as f64 * cell_h,
+                        (col - start) as f64 * cell_w,
+                        cell_h,
+                    ]);
+                } else {
+                    col += 1;
+                }
+            }
+        }
+        runs
+    }
+}
+
+// occupancy grid as it is stored in field files: one string per row, '#' for occupied cells and '.' for free ones
+#[derive(Clone, Serialize, Deserialize)]
+struct OccupancyRows {
+    area: (f64, f64, f64, f64),
+    occupancy: Vec<String>,
+}
+
+impl From<OccupancyRows> for OccupancyGrid {
+    fn from(rows: OccupancyRows) -> OccupancyGrid {
+        let cols = rows.occupancy.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut cells = Vec::with_capacity(cols * rows.occupancy.len());
+        for row in rows.occupancy.iter() {
+            cells.extend(row.bytes().map(|cell| cell == b'#'));
+            cells.extend((row.len() .. cols).map(|_| false));
+        }
+        OccupancyGrid { area: rows.area, cols, rows: rows.occupancy.len(), cells, }
+    }
+}
+
+impl From<OccupancyGrid> for OccupancyRows {
+    fn from(grid: OccupancyGrid) -> OccupancyRows {
+        OccupancyRows {
+            area: grid.area,
+            occupancy: grid.cells
+                .chunks(grid.cols.max(1))
+                .map(|row| row.iter().map(|&cell| if cell { '#' } else { '.' }).collect())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Obstacle {
+    Circle(CircleArea),
+    Polygon(PolygonArea),
+    Rectangle(RectangleArea),
+    Grid(OccupancyGrid),
+}
+
+impl Obstacle {
+    // true if the segment passes through the obstacle or closer than `clearance` to it
+    pub fn intersects_segment(&self, src: &Point, dst: &Point, clearance: f64) -> bool {
+        match self {
+            &Obstacle::Circle(ref circle) =>
+                circle.intersects_segment(src, dst, clearance),
+            &Obstacle::Polygon(ref polygon) =>
+                polygon.intersects_segment(src, dst, clearance),
+            &Obstacle::Rectangle(ref rectangle) =>
+                rectangle.intersects_segment(src, dst, clearance),
+            &Obstacle::Grid(ref grid) =>
+                grid.intersects_segment(src, dst, clearance),
+        }
+    }
+
+    // true if every point of the axis aligned square is inside the obstacle or closer than `clearance` to it, false
+    // when that can not be told for sure
+    pub fn covers_square(&self, center: &Point, half_side: f64, clearance: f64) -> bool {
+        // no point of the square is further from the obstacle than the center is plus the half diagonal
+        let reach = clearance - half_side * ::std::f64::consts::SQRT_2;
+        match self {
+            &Obstacle::Circle(ref circle) =>
+                circle.radius + reach > 0. && circle.intersects_segment(center, center, reach),
+            // the box grown by the clearance is convex, so it holds the square as soon as it holds the corners
+            &Obstacle::Rectangle(ref rectangle) =>
+                square_corners(center, half_side).iter().all(|corner| rectangle.intersects_segment(corner, corner, clearance)),
+            &Obstacle::Polygon(ref polygon) =>
+                (reach > 0. && polygon.intersects_segment(center, center, reach)) || polygon.holds_square(center, half_side),
+            &Obstacle::Grid(ref grid) =>
+                (reach > 0. && grid.intersects_segment(center, center, reach)) || grid.holds_square(center, half_side),
+        }
+    }
+}
+
+fn square_corners(center: &Point, half_side: f64) -> [Point; 4] {
+    [
+        Point { x: center.x - half_side, y: center.y - half_side, },
+        Point { x: center.x + half_side, y: center.y - half_side, },
+        Point { x: center.x + half_side, y: center.y + half_side, },
+        Point { x: center.x - half_side, y: center.y + half_side, },
+    ]
+}
+
+fn orientation(a: &Point, b: &Point, c: &Point) -> f64 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+fn on_segment(a: &Point, b: &Point, p: &Point) -> bool {
+    p.x >= a.x.min(b.x) && p.x <= a.x.max(b.x) && p.y >= a.y.min(b.y) && p.y <= a.y.max(b.y)
+}
+
+pub fn segments_intersect(pa: &Point, pb: &Point, qa: &Point, qb: &Point) -> bool {
+    let d1 = orientation(qa, qb, pa);
+    let d2 = orientation(qa, qb, pb);
+    let d3 = orientation(pa, pb, qa);
+    let d4 = orientation(pa, pb, qb);
+    if ((d1 > 0. && d2 < 0.) || (d1 < 0. && d2 > 0.)) && ((d3 > 0. && d4 < 0.) || (d3 < 0. && d4 > 0.)) {
+        return true;
+    }
+    (d1 == 0. && on_segment(qa, qb, pa)) ||
+        (d2 == 0. && on_segment(qa, qb, pb)) ||
+        (d3 == 0. && on_segment(pa, pb, qa)) ||
+        (d4 == 0. && on_segment(pa, pb, qb))
+}
+
+fn point_segment_sq_dist(p: &Point, a: &Point, b: &Point) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let sq_len = dx * dx + dy * dy;
+    if sq_len <= 0. {
+        return p.sq_dist(a);
+    }
+    let t = ((p.x - a.x) * dx + (p.y - a.y) * dy) / sq_len;
+    let t = if t < 0. { 0. } else if t > 1. { 1. } else { t };
+    p.sq_dist(&Point { x: a.x + dx * t, y: a.y + dy * t, })
+}
+
+pub fn segments_sq_dist(pa: &Point, pb: &Point, qa: &Point, qb: &Point) -> f64 {
+    if segments_intersect(pa, pb, qa, qb) {
+        return 0.;
+    }
+    point_segment_sq_dist(pa, qa, qb)
+        .min(point_segment_sq_dist(pb, qa, qb))
+        .min(point_segment_sq_dist(qa, pa, pb))
+        .min(point_segment_sq_dist(qb, pa, pb))
+}
+
+fn triangle_contains(a: &Point, b: &Point, c: &Point, p: &Point) -> bool {
+    let d1 = orientation(a, b, p);
+    let d2 = orientation(b, c, p);
+    let d3 = orientation(c, a, p);
+    let has_neg = d1 < 0. || d2 < 0. || d3 < 0.;
+    let has_pos = d1 > 0. || d2 > 0. || d3 > 0.;
+    !(has_neg && has_pos)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FieldConfig {
     pub start_area: CircleArea,
     pub finish_area: CircleArea,
@@ -56,16 +596,15 @@ impl FieldConfig {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Field {
     pub config: FieldConfig,
     pub start: Point,
-    pub obstacles: Vec<CircleArea>,
+    pub obstacles: Vec<Obstacle>,
 }
 
 impl Field {
-    pub fn generate(config: FieldConfig) -> Field {
-        let mut rng = thread_rng();
+    pub fn generate<R>(config: FieldConfig, rng: &mut R) -> Field where R: Rng {
         let rnd_radius = rng.gen_range(0., config.start_area.radius);
         let rnd_angle = rng.gen_range(0., ::std::f64::consts::PI * 2.);
         let start = Point {
@@ -76,29 +615,505 @@ impl Field {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum SampleTry {
     None,
     Blocked(Point, Point),
     Passable(Point, Point),
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct DebugImage {
     pub tick_id: usize,
     pub routes_segs: Vec<(Point, Point)>,
     pub sample_seg: SampleTry,
+    // trees of the parallel workers, one list per worker, empty when a single tree is grown
+    pub workers_segs: Vec<Vec<(Point, Point)>>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Planner {
+    Rtt,
+    RttStar,
+    RttConnect,
+    // probabilistic roadmap kept between solve requests while the obstacles stay the same
+    Prm,
+}
+
+impl Planner {
+    pub fn name(&self) -> &'static str {
+        match self {
+            &Planner::Rtt =>
+                "RTT",
+            &Planner::RttStar =>
+                "RTT*",
+            &Planner::RttConnect =>
+                "RTT-Connect",
+            &Planner::Prm =>
+                "PRM",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Planner> {
+        match name {
+            "rtt" =>
+                Some(Planner::Rtt),
+            "rtt-star" =>
+                Some(Planner::RttStar),
+            "rtt-connect" =>
+                Some(Planner::RttConnect),
+            "prm" =>
+                Some(Planner::Prm),
+            _ =>
+                None,
+        }
+    }
+
+    pub fn next(&self) -> Planner {
+        match self {
+            &Planner::Rtt =>
+                Planner::RttStar,
+            &Planner::RttStar =>
+                Planner::RttConnect,
+            &Planner::RttConnect =>
+                Planner::Prm,
+            &Planner::Prm =>
+                Planner::Rtt,
+        }
+    }
+}
+
+pub fn seeded_rng(seed: u64) -> ChaChaRng {
+    ChaChaRng::from_seed(&[seed as u32, (seed >> 32) as u32])
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Smoothing {
+    Off,
+    Shortcut,
+    Spline,
+}
+
+impl Smoothing {
+    pub fn name(&self) -> &'static str {
+        match self {
+            &Smoothing::Off =>
+                "off",
+            &Smoothing::Shortcut =>
+                "shortcut",
+            &Smoothing::Spline =>
+                "spline",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Smoothing> {
+        match name {
+            "off" =>
+                Some(Smoothing::Off),
+            "shortcut" =>
+                Some(Smoothing::Shortcut),
+            "spline" =>
+                Some(Smoothing::Spline),
+            _ =>
+                None,
+        }
+    }
+
+    pub fn next(&self) -> Smoothing {
+        match self {
+            &Smoothing::Off =>
+                Smoothing::Shortcut,
+            &Smoothing::Shortcut =>
+                Smoothing::Spline,
+            &Smoothing::Spline =>
+                Smoothing::Off,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Boundary {
+    // field border is a wall, neither route edges nor the robot footprint may cross it
+    Walled,
+    // robot footprint may hang over the field border
+    Open,
+}
+
+impl Boundary {
+    pub fn name(&self) -> &'static str {
+        match self {
+            &Boundary::Walled =>
+                "walled",
+            &Boundary::Open =>
+                "open",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Boundary> {
+        match name {
+            "walled" =>
+                Some(Boundary::Walled),
+            "open" =>
+                Some(Boundary::Open),
+            _ =>
+                None,
+        }
+    }
+
+    pub fn next(&self) -> Boundary {
+        match self {
+            &Boundary::Walled =>
+                Boundary::Open,
+            &Boundary::Open =>
+                Boundary::Walled,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Kinematics {
+    // point robot moving along straight lines in any direction
+    Holonomic,
+    // car-like robot which only drives forward with a bounded turning radius
+    Dubins,
+    // car-like robot which also drives backward
+    ReedsShepp,
+}
+
+impl Kinematics {
+    pub fn name(&self) -> &'static str {
+        match self {
+            &Kinematics::Holonomic =>
+                "holonomic",
+            &Kinematics::Dubins =>
+                "dubins",
+            &Kinematics::ReedsShepp =>
+                "reeds-shepp",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Kinematics> {
+        match name {
+            "holonomic" =>
+                Some(Kinematics::Holonomic),
+            "dubins" =>
+                Some(Kinematics::Dubins),
+            "reeds-shepp" =>
+                Some(Kinematics::ReedsShepp),
+            _ =>
+                None,
+        }
+    }
+
+    pub fn next(&self) -> Kinematics {
+        match self {
+            &Kinematics::Holonomic =>
+                Kinematics::Dubins,
+            &Kinematics::Dubins =>
+                Kinematics::ReedsShepp,
+            &Kinematics::ReedsShepp =>
+                Kinematics::Holonomic,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SolveParams {
+    pub planner: Planner,
+    // max edge length, new nodes are placed at the sample itself when not set
+    pub step: Option<f64>,
+    // probability of drawing a sample from the finish area instead of the whole field
+    pub goal_bias: f64,
+    pub smoothing: Smoothing,
+    // use uniform grid for nearest neighbour queries instead of a linear scan over all the nodes
+    pub spatial_index: bool,
+    // random generator seed for the planner, so the same field and params always produce the same tree
+    pub seed: u64,
+    // robot footprint radius: routes keep at least this distance from obstacles (and the field border when walled)
+    pub robot_radius: f64,
+    pub boundary: Boundary,
+    pub kinematics: Kinematics,
+    // minimum turning radius for the car-like kinematics
+    pub turning_radius: f64,
+    // grow the tree without collision checks and only validate edges of candidate routes (plain RTT only)
+    pub lazy: bool,
+    // keep improving the route until aborted instead of stopping after a fixed refinement (RTT* only)
+    pub anytime: bool,
+    pub budget: Budget,
+    // threads growing independent trees from consecutive seeds (plain RTT only)
+    pub workers: usize,
 }
 
+impl SolveParams {
+    // the ranges the command line flags are held to, for params coming from elsewhere
+    pub fn check(&self) -> Result<(), &'static str> {
+        if let Some(step) = self.step {
+            if !(step > 0.) {
+                return Err("step is not positive");
+            }
+        }
+        if !(self.goal_bias >= 0. && self.goal_bias <= 1.) {
+            return Err("goal bias is not a probability");
+        }
+        if !(self.robot_radius >= 0.) {
+            return Err("robot radius is negative");
+        }
+        if !(self.turning_radius > 0.) {
+            return Err("turning radius is not positive");
+        }
+        if let Some(deadline) = self.budget.deadline {
+            if !(deadline >= 0.) {
+                return Err("deadline is negative");
+            }
+        }
+        if self.workers == 0 {
+            return Err("no workers");
+        }
+        Ok(())
+    }
+
+    // planner which actually runs: car-like robots are always planned with plain RTT, since both rewiring and
+    // trees connecting assume straight edges
+    pub fn planner_in_use(&self) -> Planner {
+        match self.kinematics {
+            Kinematics::Holonomic =>
+                self.planner,
+            Kinematics::Dubins | Kinematics::ReedsShepp =>
+                Planner::Rtt,
+        }
+    }
+}
+
+// limits after which the planner gives up, none of them is set by default
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Budget {
+    // samples drawn
+    pub max_iterations: Option<usize>,
+    // tree (or roadmap) size
+    pub max_nodes: Option<usize>,
+    // wall time in seconds since the solve request has been received
+    pub deadline: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum SolveFailure {
+    IterationsExhausted,
+    NodesExhausted,
+    DeadlineExpired,
+    // the walled field is narrower than the robot footprint, so there is nowhere to plan
+    RobotTooLarge,
+    // the field or the params sent to a planner server are out of range (see `SolveParams::check`)
+    InvalidRequest,
+}
+
+impl SolveFailure {
+    pub fn description(&self) -> &'static str {
+        match self {
+            &SolveFailure::IterationsExhausted =>
+                "iterations budget exhausted",
+            &SolveFailure::NodesExhausted =>
+                "nodes budget exhausted",
+            &SolveFailure::DeadlineExpired =>
+                "deadline expired",
+            &SolveFailure::RobotTooLarge =>
+                "robot does not fit into the field",
+            &SolveFailure::InvalidRequest =>
+                "solve request is invalid",
+        }
+    }
+}
+
+// end of the field which free space around does not lead to the other end
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Enclosure {
+    Start,
+    Goal,
+}
+
+impl Enclosure {
+    pub fn description(&self) -> &'static str {
+        match self {
+            &Enclosure::Start =>
+                "start enclosed",
+            &Enclosure::Goal =>
+                "goal enclosed",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub enum MasterPacket {
-    Solve(Field),
-    SolveDebug(Field),
+    Solve(Field, SolveParams),
+    SolveDebug(Field, SolveParams),
     DebugTickAck(usize),
     Abort,
     Terminate,
 }
 
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct SolveStats {
+    pub samples: usize,
+    pub blocked: usize,
+    pub passable: usize,
+    pub nodes: usize,
+    // wall time in seconds since the solve request has been received
+    pub elapsed: f64,
+    pub route_length: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Route {
+    pub path: Vec<Point>,
+    // travel direction along every path segment, so there is one item less than path points
+    pub directions: Vec<Direction>,
+    pub smoothed: Option<Vec<Point>>,
+    // footprint radius the route has been planned for, which the current params may no longer match
+    pub robot_radius: f64,
+    pub stats: SolveStats,
+}
+
+pub fn path_length(path: &[Point]) -> f64 {
+    path.windows(2)
+        .map(|seg| seg[0].sq_dist(&seg[1]).sqrt())
+        .sum()
+}
+
+#[derive(Serialize, Deserialize)]
 pub enum SlavePacket {
-    RouteDone(Vec<Point>),
+    RouteDone(Route),
+    // a shorter route found by an anytime planner which keeps on working
+    RouteImproved(Route),
+    // the planner has given up without a route
+    RouteFailed(SolveFailure),
+    // the field has been found unsolvable before starting any planner
+    RouteInfeasible(Enclosure),
     DebugTick(DebugImage),
+    Stats(SolveStats),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use rand::Rng;
+    use serde_json;
+
+    use super::{Point, PolygonArea, RectangleArea, OccupancyGrid, orientation, seeded_rng};
+
+    #[test]
+    fn rotated_rectangle_hit_and_miss() {
+        // a 100 x 10 bar lying along the diagonal, so its bounding box holds points far away from the bar itself
+        let bar = RectangleArea { center: Point { x: 100., y: 100., }, width: 100., height: 10., angle: PI / 4., };
+        let across = (Point { x: 140., y: 110., }, Point { x: 110., y: 140., });
+        assert!(bar.intersects_segment(&across.0, &across.1, 0.));
+        // runs parallel to the bar 28.3 from its axis, that is 23.3 from its side
+        let aside = (Point { x: 120., y: 80., }, Point { x: 140., y: 100., });
+        assert!(!bar.intersects_segment(&aside.0, &aside.1, 0.));
+        assert!(!bar.intersects_segment(&aside.0, &aside.1, 20.));
+        assert!(bar.intersects_segment(&aside.0, &aside.1, 25.));
+        // a point segment is inside or outside as a whole
+        assert!(bar.intersects_segment(&Point { x: 130., y: 130., }, &Point { x: 130., y: 130., }, 0.));
+        assert!(!bar.intersects_segment(&Point { x: 130., y: 100., }, &Point { x: 130., y: 100., }, 0.));
+    }
+
+    #[test]
+    fn concave_polygon_triangulation_covers_its_area() {
+        // a "U" with a deep notch, given clockwise
+        let polygon = PolygonArea {
+            vertices: vec![
+                Point { x: 0., y: 0., },
+                Point { x: 0., y: 60., },
+                Point { x: 20., y: 60., },
+                Point { x: 20., y: 20., },
+                Point { x: 40., y: 20., },
+                Point { x: 40., y: 60., },
+                Point { x: 60., y: 60., },
+                Point { x: 60., y: 0., },
+            ],
+        };
+        let triangles = polygon.triangulate();
+        assert_eq!(triangles.len(), polygon.vertices.len() - 2);
+        let area: f64 = triangles.iter().map(|t| orientation(&t[0], &t[1], &t[2]).abs() / 2.).sum();
+        assert!((area - (60. * 60. - 20. * 40.)).abs() < 1e-9, "triangles cover {}", area);
+        for t in triangles.iter() {
+            let centroid = Point { x: (t[0].x + t[1].x + t[2].x) / 3., y: (t[0].y + t[1].y + t[2].y) / 3., };
+            assert!(polygon.contains(&centroid), "{:?} lies in the notch", t);
+        }
+    }
+
+    #[test]
+    fn grid_walk_through_a_diagonal_corner() {
+        let grid = |occupied: &[(usize, usize)]| {
+            let cells = (0 .. 9).map(|cell| occupied.contains(&(cell % 3, cell / 3))).collect();
+            OccupancyGrid::new((0., 0., 3., 3.), 3, 3, cells)
+        };
+        let (src, dst) = (Point { x: 0.5, y: 0.5, }, Point { x: 2.5, y: 2.5, });
+        // passes exactly through the corners, so only the cells on the diagonal are crossed
+        assert!(!grid(&[(1, 0), (0, 1), (2, 1), (1, 2)]).intersects_segment(&src, &dst, 0.));
+        assert!(grid(&[(1, 1)]).intersects_segment(&src, &dst, 0.));
+        assert!(grid(&[(2, 2)]).intersects_segment(&src, &dst, 0.));
+        // slightly steeper, so it crosses into the next row before the next column
+        assert!(grid(&[(0, 1)]).intersects_segment(&src, &Point { x: 2.5, y: 2.6, }, 0.));
+        // any clearance reaches the cells sharing the corners
+        assert!(grid(&[(1, 0)]).intersects_segment(&src, &dst, 0.1));
+    }
+
+    #[test]
+    fn grid_rows_round_trip() {
+        let cells = vec![
+            true, false, false, true,
+            false, false, false, false,
+            true, true, false, true,
+        ];
+        let grid = OccupancyGrid::new((0., 112., 640., 480.), 4, 3, cells);
+        let encoded = serde_json::to_string(&grid).unwrap();
+        assert_eq!(encoded, r###"{"area":[0.0,112.0,640.0,480.0],"occupancy":["#..#","....","##.#"]}"###);
+        assert_eq!(serde_json::from_str::<OccupancyGrid>(&encoded).unwrap(), grid);
+        // shorter rows are padded with free cells
+        let ragged = r##"{"area":[0.0,0.0,4.0,2.0],"occupancy":["#","..##"]}"##;
+        let cells = vec![true, false, false, false, false, false, true, true];
+        let expected = OccupancyGrid::new((0., 0., 4., 2.), 4, 2, cells);
+        assert_eq!(serde_json::from_str::<OccupancyGrid>(ragged).unwrap(), expected);
+    }
+
+    fn random_grid<R>(rng: &mut R, cols: usize, rows: usize) -> OccupancyGrid where R: Rng {
+        let cells = (0 .. cols * rows).map(|_| rng.gen::<f64>() < 0.05).collect();
+        OccupancyGrid::new((0., 100., 400., 400.), cols, rows, cells)
+    }
+
+    // every occupied cell checked as a box on its own
+    fn grid_hit_by_boxes(grid: &OccupancyGrid, src: &Point, dst: &Point, clearance: f64) -> bool {
+        let (cell_w, cell_h) = grid.cell_size();
+        (0 .. grid.rows).any(|row| (0 .. grid.cols).any(|col| {
+            grid.cells[row * grid.cols + col] && RectangleArea {
+                center: Point {
+                    x: grid.area.0 + (col as f64 + 0.5) * cell_w,
+                    y: grid.area.1 + (row as f64 + 0.5) * cell_h,
+                },
+                width: cell_w,
+                height: cell_h,
+                angle: 0.,
+            }.intersects_segment(src, dst, clearance)
+        }))
+    }
+
+    #[test]
+    fn grid_clearance_scan_agrees_with_every_cell_checked() {
+        let mut rng = seeded_rng(11);
+        for _ in 0 .. 20 {
+            let grid = random_grid(&mut rng, 57, 43);
+            for _ in 0 .. 200 {
+                let src = Point { x: rng.gen_range(-20., 420.), y: rng.gen_range(80., 420.), };
+                // short segments mostly miss on a sparse grid, while long ones mostly hit
+                let reach = if rng.gen() { 20. } else { 400. };
+                let dst = Point { x: src.x + rng.gen_range(-reach, reach), y: src.y + rng.gen_range(-reach, reach), };
+                let clearance = rng.gen_range(0.5, 15.);
+                assert_eq!(
+                    grid.intersects_segment(&src, &dst, clearance),
+                    grid_hit_by_boxes(&grid, &src, &dst, clearance),
+                    "{:?} -> {:?} with clearance {}", src, dst, clearance,
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,252 @@
+use std::f64::consts::PI;
+
+use super::common::{Point, Pose};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Turn {
+    Left,
+    Straight,
+    Right,
+}
+
+const ANGLE_EPSILON: f64 = 1e-6;
+const LENGTH_EPSILON: f64 = 1e-9;
+
+const WORDS: [[Turn; 3]; 6] = [
+    [Turn::Left, Turn::Straight, Turn::Left],
+    [Turn::Right, Turn::Straight, Turn::Right],
+    [Turn::Left, Turn::Straight, Turn::Right],
+    [Turn::Right, Turn::Straight, Turn::Left],
+    [Turn::Right, Turn::Left, Turn::Right],
+    [Turn::Left, Turn::Right, Turn::Left],
+];
+
+// shortest curve between two poses for a car which only drives forward with the given minimum turning radius
+#[derive(Clone, Debug)]
+pub struct DubinsPath {
+    start: Pose,
+    radius: f64,
+    // segments lengths are measured in turning radii
+    segments: [(Turn, f64); 3],
+}
+
+impl DubinsPath {
+    pub fn shortest(start: &Pose, end: &Pose, radius: f64) -> DubinsPath {
+        let dx = end.point.x - start.point.x;
+        let dy = end.point.y - start.point.y;
+        let d = (dx * dx + dy * dy).sqrt() / radius;
+        let theta = mod2pi(dy.atan2(dx));
+        let alpha = mod2pi(start.heading - theta);
+        let beta = mod2pi(end.heading - theta);
+
+        let mut best: Option<DubinsPath> = None;
+        for word in WORDS.iter() {
+            if let Some((t, p, q)) = word_lengths(word, alpha, beta, d) {
+                let candidate = DubinsPath {
+                    start: *start,
+                    radius,
+                    segments: [(word[0], t), (word[1], p), (word[2], q)],
+                };
+                best = match best {
+                    Some(ref current) if current.length() <= candidate.length() =>
+                        best.clone(),
+                    _ =>
+                        Some(candidate),
+                };
+            }
+        }
+        // LSL and RSR always exist
+        best.unwrap()
+    }
+
+    pub fn length(&self) -> f64 {
+        self.segments.iter().map(|&(_, len)| len).sum::<f64>() * self.radius
+    }
+
+    pub fn pose_at(&self, distance: f64) -> Pose {
+        let mut left = distance.max(0.) / self.radius;
+        let mut pose = Pose {
+            point: Point { x: 0., y: 0., },
+            heading: self.start.heading,
+        };
+        for &(turn, len) in self.segments.iter() {
+            let step = left.min(len);
+            pose = advance(&pose, turn, step);
+            left -= step;
+            if left <= 0. {
+                break;
+            }
+        }
+        Pose {
+            point: Point {
+                x: self.start.point.x + pose.point.x * self.radius,
+                y: self.start.point.y + pose.point.y * self.radius,
+            },
+            heading: mod2pi(pose.heading),
+        }
+    }
+
+    // poses along the first `length` of the curve spaced by at most `step`, the start pose is not included
+    pub fn poses(&self, length: f64, step: f64) -> Vec<Pose> {
+        let count = (length / step).ceil() as usize;
+        (1 ..= count)
+            .map(|i| self.pose_at(length * i as f64 / count as f64))
+            .collect()
+    }
+}
+
+// moves along a single segment, negative `len` (in turning radii) drives backward
+pub fn advance(pose: &Pose, turn: Turn, len: f64) -> Pose {
+    let (x, y, h) = (pose.point.x, pose.point.y, pose.heading);
+    let (x, y, h) = match turn {
+        Turn::Left =>
+            (x + (h + len).sin() - h.sin(), y - (h + len).cos() + h.cos(), h + len),
+        Turn::Right =>
+            (x - (h - len).sin() + h.sin(), y + (h - len).cos() - h.cos(), h - len),
+        Turn::Straight =>
+            (x + h.cos() * len, y + h.sin() * len, h),
+    };
+    Pose { point: Point { x, y, }, heading: h, }
+}
+
+// angles a rounding error short of a full turn are snapped to zero: a curve recomputed to a pose lying on another
+// curve (which the planners do whenever they steer to a truncated extension) should end with an empty segment, but
+// rounding in `pose_at` leaves it at -1e-15 or so, which would otherwise wrap into a whole extra loop. `ANGLE_EPSILON`
+// keeps the snap a few orders over that noise while turning the car by no more than a micron per metre of radius.
+// `LENGTH_EPSILON` does the same for the squared straight length, which goes slightly negative when such a pose sits
+// right at the end of an arc and would otherwise rule the actual shortest word out.
+pub fn mod2pi(angle: f64) -> f64 {
+    let angle = angle - 2. * PI * (angle / (2. * PI)).floor();
+    if 2. * PI - angle < ANGLE_EPSILON {
+        0.
+    } else {
+        angle
+    }
+}
+
+// segments lengths for the given word in the normalized frame (see "Classification of the Dubins set" by Shkel and Lumelsky)
+fn word_lengths(word: &[Turn; 3], alpha: f64, beta: f64, d: f64) -> Option<(f64, f64, f64)> {
+    let (sa, ca) = alpha.sin_cos();
+    let (sb, cb) = beta.sin_cos();
+    let c_ab = (alpha - beta).cos();
+    match *word {
+        [Turn::Left, Turn::Straight, Turn::Left] => {
+            let p_sq = 2. + d * d - 2. * c_ab + 2. * d * (sa - sb);
+            if p_sq < -LENGTH_EPSILON {
+                return None;
+            }
+            let tmp = (cb - ca).atan2(d + sa - sb);
+            Some((mod2pi(tmp - alpha), p_sq.max(0.).sqrt(), mod2pi(beta - tmp)))
+        },
+        [Turn::Right, Turn::Straight, Turn::Right] => {
+            let p_sq = 2. + d * d - 2. * c_ab + 2. * d * (sb - sa);
+            if p_sq < -LENGTH_EPSILON {
+                return None;
+            }
+            let tmp = (ca - cb).atan2(d - sa + sb);
+            Some((mod2pi(alpha - tmp), p_sq.max(0.).sqrt(), mod2pi(tmp - beta)))
+        },
+        [Turn::Left, Turn::Straight, Turn::Right] => {
+            let p_sq = -2. + d * d + 2. * c_ab + 2. * d * (sa + sb);
+            if p_sq < -LENGTH_EPSILON {
+                return None;
+            }
+            let p = p_sq.max(0.).sqrt();
+            let tmp = (-ca - cb).atan2(d + sa + sb) - (-2.0f64).atan2(p);
+            Some((mod2pi(tmp - alpha), p, mod2pi(tmp - beta)))
+        },
+        [Turn::Right, Turn::Straight, Turn::Left] => {
+            let p_sq = -2. + d * d + 2. * c_ab - 2. * d * (sa + sb);
+            if p_sq < -LENGTH_EPSILON {
+                return None;
+            }
+            let p = p_sq.max(0.).sqrt();
+            let tmp = (ca + cb).atan2(d - sa - sb) - 2.0f64.atan2(p);
+            Some((mod2pi(alpha - tmp), p, mod2pi(beta - tmp)))
+        },
+        [Turn::Right, Turn::Left, Turn::Right] => {
+            let tmp = (6. - d * d + 2. * c_ab + 2. * d * (sa - sb)) / 8.;
+            if tmp.abs() > 1. {
+                return None;
+            }
+            let p = mod2pi(2. * PI - tmp.acos());
+            let t = mod2pi(alpha - (ca - cb).atan2(d - sa + sb) + p / 2.);
+            Some((t, p, mod2pi(alpha - beta - t + p)))
+        },
+        [Turn::Left, Turn::Right, Turn::Left] => {
+            let tmp = (6. - d * d + 2. * c_ab + 2. * d * (sb - sa)) / 8.;
+            if tmp.abs() > 1. {
+                return None;
+            }
+            let p = mod2pi(2. * PI - tmp.acos());
+            let t = mod2pi(-alpha - (ca - cb).atan2(d + sa - sb) + p / 2.);
+            Some((t, p, mod2pi(beta - alpha - t + p)))
+        },
+        _ =>
+            None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use rand::Rng;
+
+    use super::{DubinsPath, mod2pi};
+    use super::super::common::{Point, Pose, seeded_rng};
+
+    const RADIUS: f64 = 20.;
+
+    #[test]
+    fn mod2pi_snaps_a_rounding_error_short_of_a_full_turn() {
+        assert_eq!(mod2pi(2. * PI - 1e-12), 0.);
+        assert_eq!(mod2pi(-1e-15), 0.);
+        assert!((mod2pi(2. * PI - 1e-3) - (2. * PI - 1e-3)).abs() < 1e-12);
+        assert!((mod2pi(3. * PI) - PI).abs() < 1e-12);
+    }
+
+    // steering to a pose already lying on a curve has to follow that very curve rather than loop around on the way
+    #[test]
+    fn recomputed_curve_to_a_pose_on_it_gains_no_loop() {
+        let mut rng = seeded_rng(5);
+        for _ in 0 .. 500 {
+            let start = Pose {
+                point: Point { x: rng.gen_range(0., 200.), y: rng.gen_range(0., 200.), },
+                heading: rng.gen_range(0., 2. * PI),
+            };
+            let end = Pose {
+                point: Point { x: rng.gen_range(0., 200.), y: rng.gen_range(0., 200.), },
+                heading: rng.gen_range(0., 2. * PI),
+            };
+            let curve = DubinsPath::shortest(&start, &end, RADIUS);
+            for &fraction in [0.25, 0.5, 0.75, 1.].iter() {
+                let distance = curve.length() * fraction;
+                let pose = curve.pose_at(distance);
+                let recomputed = DubinsPath::shortest(&start, &pose, RADIUS);
+                assert!((recomputed.length() - distance).abs() < 1e-6,
+                        "{:?} -> {:?}: {} instead of {}", start, pose, recomputed.length(), distance);
+            }
+        }
+    }
+
+    #[test]
+    fn curve_ends_at_the_target_pose() {
+        let mut rng = seeded_rng(6);
+        for _ in 0 .. 500 {
+            let start = Pose {
+                point: Point { x: rng.gen_range(0., 200.), y: rng.gen_range(0., 200.), },
+                heading: rng.gen_range(0., 2. * PI),
+            };
+            let end = Pose {
+                point: Point { x: rng.gen_range(0., 200.), y: rng.gen_range(0., 200.), },
+                heading: rng.gen_range(0., 2. * PI),
+            };
+            let curve = DubinsPath::shortest(&start, &end, RADIUS);
+            let reached = curve.pose_at(curve.length());
+            assert!(reached.point.sq_dist(&end.point) < 1e-12, "{:?} instead of {:?}", reached, end);
+            let heading_error = mod2pi(reached.heading - end.heading + PI) - PI;
+            assert!(heading_error.abs() < 1e-9, "{:?} instead of {:?}", reached, end);
+        }
+    }
+}
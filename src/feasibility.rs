@@ -0,0 +1,260 @@
+//! Pre-solve check of whether the finish area can be reached from the start at all, so that a planner is never
+//! left sampling a field where the start or the finish is walled off by obstacles.
+//!
+//! The check only ever reports an enclosure it can prove: a lattice cell is closed only when an obstacle grown by
+//! the robot radius covers the whole of it (or the cell lies wholly outside the walls), while every other cell
+//! counts as open to all of its eight neighbours. Any route the robot could drive thus stays within the cells
+//! flooded from the start, and a field the lattice is too coarse to tell about is simply left to the planner.
+
+use std::collections::VecDeque;
+
+use super::common::{Field, Point, CircleArea, Obstacle, Enclosure};
+
+// lattice pitch is half the robot radius, which keeps the closed cells along even a thin wall in an unbroken band,
+// within limits keeping the lattice both small enough to fill quickly and fine enough to tell passages apart
+const MIN_CELL_SIZE: f64 = 2.;
+const MAX_CELL_SIZE: f64 = 8.;
+
+struct Lattice {
+    min_x: f64,
+    min_y: f64,
+    cell_size: f64,
+    cols: usize,
+    rows: usize,
+}
+
+impl Lattice {
+    fn new(field_area: (f64, f64, f64, f64), cell_size: f64) -> Lattice {
+        let (min_x, min_y, max_x, max_y) = field_area;
+        Lattice {
+            min_x,
+            min_y,
+            cell_size,
+            cols: ((max_x - min_x) / cell_size).ceil().max(1.) as usize,
+            rows: ((max_y - min_y) / cell_size).ceil().max(1.) as usize,
+        }
+    }
+
+    fn center(&self, cell: usize) -> Point {
+        Point {
+            x: self.min_x + ((cell % self.cols) as f64 + 0.5) * self.cell_size,
+            y: self.min_y + ((cell / self.cols) as f64 + 0.5) * self.cell_size,
+        }
+    }
+
+    fn cell_of(&self, point: &Point) -> usize {
+        let clamp = |value: f64, limit: usize| {
+            if value < 0. { 0 } else if value >= limit as f64 { limit - 1 } else { value as usize }
+        };
+        let col = clamp(((point.x - self.min_x) / self.cell_size).floor(), self.cols);
+        let row = clamp(((point.y - self.min_y) / self.cell_size).floor(), self.rows);
+        row * self.cols + col
+    }
+
+    // the eight neighbours of the cell
+    fn around(&self, cell: usize) -> Vec<usize> {
+        let (col, row) = ((cell % self.cols) as isize, (cell / self.cols) as isize);
+        let mut cells = Vec::with_capacity(8);
+        for y in row - 1 ..= row + 1 {
+            for x in col - 1 ..= col + 1 {
+                if (x, y) != (col, row) && x >= 0 && y >= 0 && (x as usize) < self.cols && (y as usize) < self.rows {
+                    cells.push(y as usize * self.cols + x as usize);
+                }
+            }
+        }
+        cells
+    }
+
+    // whether some point of the cell lies inside the circle
+    fn touches(&self, cell: usize, circle: &CircleArea) -> bool {
+        let center = self.center(cell);
+        let half = self.cell_size / 2.;
+        let dx = ((circle.center.x - center.x).abs() - half).max(0.);
+        let dy = ((circle.center.y - center.y).abs() - half).max(0.);
+        dx * dx + dy * dy < circle.radius * circle.radius
+    }
+}
+
+enum Flood {
+    // a cell satisfying the target has been visited
+    Reached,
+    // every reachable cell has been visited without meeting the target
+    Exhausted(usize),
+    // the region has grown over the given limit
+    Overflow,
+}
+
+// breadth first walk over the open lattice cells
+fn flood<T>(lattice: &Lattice, closed: &[bool], seeds: Vec<usize>, limit: usize, is_target: T) -> Flood
+    where T: Fn(usize) -> bool
+{
+    let mut visited = closed.to_vec();
+    let mut queue = VecDeque::new();
+    for cell in seeds {
+        if !visited[cell] {
+            visited[cell] = true;
+            queue.push_back(cell);
+        }
+    }
+    let mut count = queue.len();
+    while let Some(cell) = queue.pop_front() {
+        if is_target(cell) {
+            return Flood::Reached;
+        }
+        for next in lattice.around(cell) {
+            if !visited[next] {
+                visited[next] = true;
+                queue.push_back(next);
+                count += 1;
+                if count > limit {
+                    return Flood::Overflow;
+                }
+            }
+        }
+    }
+    Flood::Exhausted(count)
+}
+
+// `None` unless the finish area is proven out of reach for a robot of radius `clearance` whose center has to stay
+// within `free_area`, otherwise tells which end is enclosed: the smaller of the start and the finish regions is blamed
+pub fn check(field: &Field, free_area: (f64, f64, f64, f64), clearance: f64) -> Option<Enclosure> {
+    let finish = &field.config.finish_area;
+    if field.start.sq_dist(&finish.center) < finish.radius * finish.radius {
+        return None;
+    }
+    let lattice = Lattice::new(field.config.field_area, (clearance / 2.).max(MIN_CELL_SIZE).min(MAX_CELL_SIZE));
+    let half = lattice.cell_size / 2.;
+    let (min_x, min_y, max_x, max_y) = free_area;
+    let closed: Vec<bool> = (0 .. lattice.cols * lattice.rows)
+        .map(|cell| {
+            let center = lattice.center(cell);
+            center.x + half < min_x || center.x - half > max_x || center.y + half < min_y || center.y - half > max_y ||
+                field.obstacles.iter().any(|obstacle| obstacle.covers_square(&center, half, clearance))
+        })
+        .collect();
+    let in_finish = |cell: usize| lattice.touches(cell, finish);
+
+    let start_region = match flood(&lattice, &closed, vec![lattice.cell_of(&field.start)], usize::max_value(), &in_finish) {
+        Flood::Reached =>
+            return None,
+        Flood::Exhausted(count) =>
+            count,
+        Flood::Overflow =>
+            usize::max_value(),
+    };
+
+    let finish_seeds: Vec<_> = (0 .. lattice.cols * lattice.rows)
+        .filter(|&cell| !closed[cell] && in_finish(cell))
+        .collect();
+    if finish_seeds.is_empty() {
+        return Some(Enclosure::Goal);
+    }
+    match flood(&lattice, &closed, finish_seeds, start_region, |_| false) {
+        Flood::Overflow =>
+            Some(Enclosure::Start),
+        Flood::Reached | Flood::Exhausted(..) =>
+            Some(Enclosure::Goal),
+    }
+}
+
+// outcome of the check along with what it has been made for: it is only reused while all of these stay the same
+pub struct Verdict {
+    field_area: (f64, f64, f64, f64),
+    start: Point,
+    finish_area: CircleArea,
+    obstacles: Vec<Obstacle>,
+    free_area: (f64, f64, f64, f64),
+    clearance: f64,
+
+    pub enclosure: Option<Enclosure>,
+}
+
+impl Verdict {
+    pub fn new(field: &Field, free_area: (f64, f64, f64, f64), clearance: f64) -> Verdict {
+        Verdict {
+            field_area: field.config.field_area,
+            start: field.start,
+            finish_area: field.config.finish_area,
+            obstacles: field.obstacles.clone(),
+            free_area,
+            clearance,
+            enclosure: check(field, free_area, clearance),
+        }
+    }
+
+    pub fn fits(&self, field: &Field, free_area: (f64, f64, f64, f64), clearance: f64) -> bool {
+        self.field_area == field.config.field_area &&
+            self.start == field.start &&
+            self.finish_area == field.config.finish_area &&
+            self.obstacles == field.obstacles &&
+            self.free_area == free_area &&
+            self.clearance == clearance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+    use super::super::common::{Field, FieldConfig, Point, CircleArea, PolygonArea, Obstacle, Enclosure};
+
+    const ROBOT_RADIUS: f64 = 6.;
+
+    // 1 pixel thick walls cutting off the corner with the start, the vertical one leaves a gap of the given width
+    fn walled_start(gap: f64) -> Field {
+        let wall = |min_x: f64, min_y: f64, max_x: f64, max_y: f64| Obstacle::Polygon(PolygonArea {
+            vertices: vec![
+                Point { x: min_x, y: min_y, },
+                Point { x: max_x, y: min_y, },
+                Point { x: max_x, y: max_y, },
+                Point { x: min_x, y: max_y, },
+            ],
+        });
+        Field {
+            config: FieldConfig::new(0., 0., 400., 300.),
+            start: Point { x: 40., y: 40., },
+            obstacles: vec![
+                wall(0., 100., 100., 101.),
+                wall(100., 0., 101., 50.),
+                wall(100., 50. + gap, 101., 101.),
+            ],
+        }
+    }
+
+    fn free_area(field: &Field) -> (f64, f64, f64, f64) {
+        let (min_x, min_y, max_x, max_y) = field.config.field_area;
+        (min_x + ROBOT_RADIUS, min_y + ROBOT_RADIUS, max_x - ROBOT_RADIUS, max_y - ROBOT_RADIUS)
+    }
+
+    #[test]
+    fn closed_walls_enclose_the_start() {
+        let field = walled_start(0.);
+        match check(&field, free_area(&field), ROBOT_RADIUS) {
+            Some(Enclosure::Start) =>
+                (),
+            other =>
+                panic!("{:?} instead of the start enclosed", other),
+        }
+    }
+
+    // whatever the lattice alignment, a gap the robot fits through is never reported closed
+    #[test]
+    fn passable_gap_is_never_reported() {
+        for step in 0 .. 20 {
+            let field = walled_start(2. * ROBOT_RADIUS + 0.5 + step as f64 * 0.37);
+            assert!(check(&field, free_area(&field), ROBOT_RADIUS).is_none(), "{:?}", field.obstacles[2]);
+        }
+    }
+
+    #[test]
+    fn covered_finish_encloses_the_goal() {
+        let mut field = walled_start(50.);
+        let finish = field.config.finish_area;
+        field.obstacles.push(Obstacle::Circle(CircleArea { center: finish.center, radius: finish.radius * 2., }));
+        match check(&field, free_area(&field), ROBOT_RADIUS) {
+            Some(Enclosure::Goal) =>
+                (),
+            other =>
+                panic!("{:?} instead of the goal enclosed", other),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Fields are stored as JSON documents mirroring the `Field` structure:
+//!
+//! ```json
+//! {
+//!   "config": {
+//!     "start_area": { "center": { "x": 40.0, "y": 152.0 }, "radius": 20.0 },
+//!     "finish_area": { "center": { "x": 600.0, "y": 440.0 }, "radius": 20.0 },
+//!     "field_area": [0.0, 112.0, 640.0, 480.0]
+//!   },
+//!   "start": { "x": 43.5, "y": 149.2 },
+//!   "obstacles": [
+//!     { "center": { "x": 320.0, "y": 260.0 }, "radius": 120.0 },
+//!     { "vertices": [ { "x": 100.0, "y": 300.0 }, { "x": 200.0, "y": 300.0 }, { "x": 150.0, "y": 400.0 } ] },
+//!     { "center": { "x": 500.0, "y": 200.0 }, "width": 200.0, "height": 20.0, "angle": 0.5 }
+//!   ]
+//! }
+//! ```
+//!
+//! `field_area` is `[min_x, min_y, max_x, max_y]` in window pixels, `start` should lie inside the field. The numbers
+//! here are only illustrative: they match a 640x480 window, whose field starts below the console strip, but a field
+//! file may use any area.
+//! An obstacle is either a circle (`center` and `radius`), a simple polygon (`vertices`, convex or concave)
+//! or a rectangle (`center`, `width`, `height` and an optional rotation `angle` in radians).
+//! Maps imported with `--map` are stored as occupancy grids: `area` they are stretched over and `occupancy` rows
+//! of `#` (occupied) and `.` (free) cells, e.g. `{ "area": [0.0, 112.0, 640.0, 480.0], "occupancy": ["#..", ".##"] }`.
+
+use std::{io, fs};
+use std::path::Path;
+
+use serde_json;
+
+use super::common::{Field, Obstacle};
+
+#[derive(Debug)]
+pub enum Error {
+    Open(io::Error),
+    Create(io::Error),
+    Read(serde_json::Error),
+    Write(serde_json::Error),
+    Invalid(&'static str),
+}
+
+pub fn load<P>(path: P) -> Result<Field, Error> where P: AsRef<Path> {
+    let file = fs::File::open(path)
+        .map_err(Error::Open)?;
+    let field = serde_json::from_reader(io::BufReader::new(file))
+        .map_err(Error::Read)?;
+    check(&field).map_err(Error::Invalid)?;
+    Ok(field)
+}
+
+// lattices and indices the planner lays over the field grow with its area, so it is bounded
+const MAX_FIELD_SIDE: f64 = 10000.;
+
+// rejects fields the planner can not even sample, instead of letting it panic on them later
+pub fn check(field: &Field) -> Result<(), &'static str> {
+    let (min_x, min_y, max_x, max_y) = field.config.field_area;
+    if !(min_x < max_x && min_y < max_y) {
+        return Err("field_area is empty");
+    }
+    if !(max_x - min_x <= MAX_FIELD_SIDE && max_y - min_y <= MAX_FIELD_SIDE) {
+        return Err("field_area is too large");
+    }
+    if !(field.config.start_area.radius > 0.) {
+        return Err("start_area radius is not positive");
+    }
+    if !(field.config.finish_area.radius > 0.) {
+        return Err("finish_area radius is not positive");
+    }
+    let start = &field.start;
+    if !(start.x >= min_x && start.x <= max_x && start.y >= min_y && start.y <= max_y) {
+        return Err("start lies outside of field_area");
+    }
+    for obstacle in field.obstacles.iter() {
+        if let &Obstacle::Circle(ref circle) = obstacle {
+            if !(circle.radius > 0.) {
+                return Err("circle obstacle radius is not positive");
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn save<P>(path: P, field: &Field) -> Result<(), Error> where P: AsRef<Path> {
+    let file = fs::File::create(path)
+        .map_err(Error::Create)?;
+    serde_json::to_writer_pretty(io::BufWriter::new(file), field)
+        .map_err(Error::Write)
+}
@@ -0,0 +1,81 @@
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use super::{spawn_slave, Error};
+use super::common::{
+    Point,
+    Field,
+    SolveParams,
+    SolveStats,
+    MasterPacket,
+    SlavePacket,
+    path_length,
+};
+
+pub fn run(field: Field, params: SolveParams, planner_addr: Option<&str>) -> Result<(), Error> {
+    let (master_tx, master_rx, slave) = spawn_slave(planner_addr)?;
+
+    let seed = params.seed;
+    let now = Instant::now();
+    master_tx.send(MasterPacket::Solve(field, params))
+        .map_err(|_| Error::SlaveDisconnected)?;
+    let mut last_stats = None;
+    let outcome = loop {
+        match master_rx.recv() {
+            Ok(SlavePacket::RouteDone(route)) =>
+                break Ok(route),
+            Ok(SlavePacket::RouteFailed(failure)) =>
+                break Err((failure.description(), Error::NoRoute(failure))),
+            Ok(SlavePacket::RouteInfeasible(enclosure)) =>
+                break Err((enclosure.description(), Error::RouteInfeasible(enclosure))),
+            Ok(SlavePacket::RouteImproved(route)) =>
+                println!("improved route length: {:.3} ({:.3}s)", path_length(&route.path), route.stats.elapsed),
+            Ok(SlavePacket::DebugTick(..)) =>
+                (),
+            Ok(SlavePacket::Stats(stats)) =>
+                last_stats = Some(stats),
+            Err(mpsc::RecvError) =>
+                return Err(Error::SlaveDisconnected),
+        }
+    };
+    let elapsed = now.elapsed();
+
+    master_tx.send(MasterPacket::Terminate).ok();
+    let () = slave.join().map_err(Error::ThreadJoin)?;
+
+    println!("seed: {}", seed);
+    let route = match outcome {
+        Ok(route) =>
+            route,
+        Err((description, error)) => {
+            println!("no route: {}", description);
+            if let Some(ref stats) = last_stats {
+                print_stats(stats, elapsed);
+            }
+            return Err(error);
+        },
+    };
+    println!("route: {}", format_path(&route.path));
+    println!("route length: {:.3}", path_length(&route.path));
+    println!("cusps: {}", route.directions.windows(2).filter(|pair| pair[0] != pair[1]).count());
+    if let Some(ref smoothed) = route.smoothed {
+        println!("smoothed: {}", format_path(smoothed));
+        println!("smoothed length: {:.3}", path_length(smoothed));
+    }
+    print_stats(&route.stats, elapsed);
+
+    Ok(())
+}
+
+fn print_stats(stats: &SolveStats, elapsed: Duration) {
+    println!("samples: {} (blocked: {}, passable: {})", stats.samples, stats.blocked, stats.passable);
+    println!("nodes: {}", stats.nodes);
+    println!("elapsed: {:.3}s", elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000.);
+}
+
+fn format_path(path: &[Point]) -> String {
+    path.iter()
+        .map(|point| format!("{:.3},{:.3}", point.x, point.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -1,16 +1,22 @@
 extern crate rtt;
 extern crate rand;
+extern crate serde;
+extern crate serde_json;
+extern crate image;
 extern crate gfx_core;
 extern crate env_logger;
 extern crate piston_window;
 #[macro_use] extern crate log;
 #[macro_use] extern crate clap;
+#[macro_use] extern crate serde_derive;
 
 use std::{io, thread, process};
 use std::sync::mpsc;
 use std::path::PathBuf;
+use std::str::FromStr;
 
-use clap::Arg;
+use rand::Rng;
+use clap::{Arg, ArgMatches, SubCommand};
 use piston_window::{
     OpenGL,
     PistonWindow,
@@ -28,15 +34,41 @@ use piston_window::{
 };
 
 mod common;
+mod spatial;
+mod state_space;
+mod feasibility;
+mod dubins;
+mod reeds_shepp;
 mod rtt_slave;
+mod headless;
+mod remote;
+mod field_file;
+mod map_image;
 
 use common::{
     Point,
+    Direction,
     CircleArea,
+    PolygonArea,
+    RectangleArea,
+    Obstacle,
+    OccupancyGrid,
     Field,
     FieldConfig,
     DebugImage,
     SampleTry,
+    Route,
+    Planner,
+    Smoothing,
+    Boundary,
+    Kinematics,
+    SolveParams,
+    SolveFailure,
+    Enclosure,
+    Budget,
+    SolveStats,
+    path_length,
+    seeded_rng,
     MasterPacket,
     SlavePacket,
 };
@@ -56,9 +88,16 @@ fn main() {
 #[derive(Debug)]
 enum Error {
     MissingParameter(&'static str),
+    InvalidParameter { param: &'static str, value: String, },
     Piston(PistonError),
     ThreadSpawn(io::Error),
     ThreadJoin(Box<std::any::Any + Send + 'static>),
+    SlaveDisconnected,
+    LoadField { file: String, error: field_file::Error, },
+    LoadMap { file: String, error: map_image::Error, },
+    NoRoute(SolveFailure),
+    RouteInfeasible(Enclosure),
+    Remote(remote::Error),
 }
 
 #[derive(Debug)]
@@ -68,9 +107,334 @@ enum PistonError {
     DrawText(gfx_core::factory::CombinedError),
 }
 
-const CONSOLE_HEIGHT: u32 = 32;
+// console strip above the field: the info line followed by `Env::console_lines`
+const CONSOLE_LINES: usize = 4;
+const CONSOLE_INFO_HEIGHT: u32 = 32;
+const CONSOLE_LINE_HEIGHT: u32 = 20;
+const CONSOLE_HEIGHT: u32 = CONSOLE_INFO_HEIGHT + CONSOLE_LINE_HEIGHT * CONSOLE_LINES as u32;
+const GOAL_BIAS_STEP: f64 = 0.05;
+const ROBOT_RADIUS_STEP: f64 = 2.;
 const SCREEN_WIDTH: u32 = 640;
 const SCREEN_HEIGHT: u32 = 480;
+const DEFAULT_FIELD_FILE: &'static str = "field.json";
+// colors of the parallel workers trees in debug view, cycled when there are more workers
+const WORKER_COLORS: [[f32; 4]; 6] = [
+    [0.15, 0.15, 0., 1.0],
+    [0., 0.3, 0.6, 1.0],
+    [0.5, 0., 0.5, 1.0],
+    [0., 0.45, 0.3, 1.0],
+    [0.6, 0.35, 0., 1.0],
+    [0.35, 0.35, 0.35, 1.0],
+];
+// clicking this close to the first vertex of a polygon being created closes it
+const POLYGON_CLOSE_DISTANCE: f64 = 8.;
+
+fn solve_params_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("planner")
+            .short("p")
+            .long("planner")
+            .value_name("PLANNER")
+            .help("Path planning algorithm")
+            .possible_values(&["rtt", "rtt-star", "rtt-connect", "prm"])
+            .default_value("rtt")
+            .takes_value(true),
+        Arg::with_name("step-size")
+            .short("s")
+            .long("step-size")
+            .value_name("PIXELS")
+            .help("Max tree edge length (new nodes are placed right at the samples if omitted)")
+            .takes_value(true),
+        Arg::with_name("goal-bias")
+            .short("b")
+            .long("goal-bias")
+            .value_name("PROBABILITY")
+            .help("Probability of sampling inside the finish area")
+            .default_value("0")
+            .takes_value(true),
+        Arg::with_name("smoothing")
+            .short("m")
+            .long("smoothing")
+            .value_name("MODE")
+            .help("Solved route post processing")
+            .possible_values(&["off", "shortcut", "spline"])
+            .default_value("off")
+            .takes_value(true),
+        Arg::with_name("linear-scan")
+            .short("l")
+            .long("linear-scan")
+            .help("Find nearest tree nodes with a linear scan instead of the spatial index"),
+        Arg::with_name("seed")
+            .short("r")
+            .long("seed")
+            .value_name("SEED")
+            .help("Random generator seed for planning, and for field generation unless --field-seed is given (a random one is used if omitted)")
+            .takes_value(true),
+        Arg::with_name("field-seed")
+            .long("field-seed")
+            .value_name("SEED")
+            .help("Random generator seed for field generation (the planning seed is used if omitted)")
+            .takes_value(true),
+        Arg::with_name("robot-radius")
+            .long("robot-radius")
+            .value_name("PIXELS")
+            .help("Robot footprint radius, routes keep at least this distance from obstacles and walls")
+            .default_value("0")
+            .takes_value(true),
+        Arg::with_name("boundary")
+            .long("boundary")
+            .value_name("MODE")
+            .help("Whether the field border is a wall or the robot footprint may hang over it")
+            .possible_values(&["walled", "open"])
+            .default_value("walled")
+            .takes_value(true),
+        Arg::with_name("kinematics")
+            .short("k")
+            .long("kinematics")
+            .value_name("MODEL")
+            .help("Robot motion model: a point moving in any direction or a car-like robot with a bounded turning radius (forward only or reversing)")
+            .possible_values(&["holonomic", "dubins", "reeds-shepp"])
+            .default_value("holonomic")
+            .takes_value(true),
+        Arg::with_name("turning-radius")
+            .long("turning-radius")
+            .value_name("PIXELS")
+            .help("Minimum turning radius of the car-like robot")
+            .default_value("20")
+            .takes_value(true),
+        Arg::with_name("lazy")
+            .long("lazy")
+            .help("Skip collision checks while growing the tree and only check edges of the routes reaching the finish (plain RTT only)"),
+        Arg::with_name("anytime")
+            .long("anytime")
+            .help("Keep improving the route until aborted, reporting every shorter one found (RTT* only)"),
+        Arg::with_name("workers")
+            .long("workers")
+            .value_name("THREADS")
+            .help("Grow this many independent trees in parallel: the first route wins, or the shortest one once every tree is out of budget when a budget is given (plain RTT only)")
+            .default_value("1")
+            .takes_value(true),
+        Arg::with_name("max-iterations")
+            .long("max-iterations")
+            .value_name("SAMPLES")
+            .help("Give up after drawing this many samples")
+            .takes_value(true),
+        Arg::with_name("max-nodes")
+            .long("max-nodes")
+            .value_name("NODES")
+            .help("Give up once the tree (or the roadmap) grows this large")
+            .takes_value(true),
+        Arg::with_name("deadline")
+            .long("deadline")
+            .value_name("SECONDS")
+            .help("Give up after planning for this long")
+            .takes_value(true),
+    ]
+}
+
+fn planner_addr_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("planner-addr")
+        .long("planner-addr")
+        .value_name("ADDR")
+        .help("Plan on a server started with `rtt-demo serve` at HOST:PORT or unix:PATH instead of in process")
+        .takes_value(true)
+}
+
+// planner thread in process or the link to a planner server, talking over the returned channels
+fn spawn_slave(planner_addr: Option<&str>) -> Result<(mpsc::Sender<MasterPacket>, mpsc::Receiver<SlavePacket>, thread::JoinHandle<()>), Error> {
+    let (master_tx, slave_rx) = mpsc::channel();
+    let (slave_tx, master_rx) = mpsc::channel();
+
+    let slave = if let Some(planner_addr) = planner_addr {
+        let connection = remote::connect(&remote::Addr::parse(planner_addr))
+            .map_err(Error::Remote)?;
+        thread::Builder::new()
+            .name("RTT demo planner client".to_string())
+            .spawn(move || if let Err(e) = remote::run_client(connection, slave_rx, slave_tx) {
+                error!("planner link failed: {:?}", e);
+            })
+            .map_err(Error::ThreadSpawn)?
+    } else {
+        thread::Builder::new()
+            .name("RTT demo slave".to_string())
+            .spawn(move || rtt_slave::run(slave_rx, slave_tx))
+            .map_err(Error::ThreadSpawn)?
+    };
+    Ok((master_tx, master_rx, slave))
+}
+
+fn map_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("map")
+            .long("map")
+            .value_name("IMAGE")
+            .help("Occupancy grid bitmap (e.g. a floor plan) stretched over the whole field, dark pixels are obstacles")
+            .takes_value(true),
+        Arg::with_name("map-threshold")
+            .long("map-threshold")
+            .value_name("BRIGHTNESS")
+            .help("Map pixels with grayscale brightness (0 - 255) below this value are obstacles")
+            .default_value("128")
+            .takes_value(true),
+    ]
+}
+
+fn parse_param<T, F>(matches: &ArgMatches, param: &'static str, is_valid: F) -> Result<T, Error>
+    where T: FromStr, F: Fn(&T) -> bool
+{
+    let value = matches.value_of(param)
+        .ok_or(Error::MissingParameter(param))?;
+    match value.parse() {
+        Ok(parsed) =>
+            if is_valid(&parsed) {
+                Ok(parsed)
+            } else {
+                Err(Error::InvalidParameter { param, value: value.to_string(), })
+            },
+        Err(..) =>
+            Err(Error::InvalidParameter { param, value: value.to_string(), }),
+    }
+}
+
+fn solve_params(matches: &ArgMatches) -> Result<SolveParams, Error> {
+    let planner_value = matches.value_of("planner")
+        .ok_or(Error::MissingParameter("planner"))?;
+    let planner = Planner::from_name(planner_value)
+        .ok_or_else(|| Error::InvalidParameter { param: "planner", value: planner_value.to_string(), })?;
+    let step = if matches.is_present("step-size") {
+        Some(parse_param(matches, "step-size", |step: &f64| *step > 0.)?)
+    } else {
+        None
+    };
+    let goal_bias = parse_param(matches, "goal-bias", |goal_bias: &f64| *goal_bias >= 0. && *goal_bias <= 1.)?;
+    let smoothing_value = matches.value_of("smoothing")
+        .ok_or(Error::MissingParameter("smoothing"))?;
+    let smoothing = Smoothing::from_name(smoothing_value)
+        .ok_or_else(|| Error::InvalidParameter { param: "smoothing", value: smoothing_value.to_string(), })?;
+
+    let seed = if matches.is_present("seed") {
+        parse_param(matches, "seed", |_: &u64| true)?
+    } else {
+        random_seed()
+    };
+    let robot_radius = parse_param(matches, "robot-radius", |radius: &f64| *radius >= 0.)?;
+    let boundary_value = matches.value_of("boundary")
+        .ok_or(Error::MissingParameter("boundary"))?;
+    let boundary = Boundary::from_name(boundary_value)
+        .ok_or_else(|| Error::InvalidParameter { param: "boundary", value: boundary_value.to_string(), })?;
+    let kinematics_value = matches.value_of("kinematics")
+        .ok_or(Error::MissingParameter("kinematics"))?;
+    let kinematics = Kinematics::from_name(kinematics_value)
+        .ok_or_else(|| Error::InvalidParameter { param: "kinematics", value: kinematics_value.to_string(), })?;
+    let turning_radius = parse_param(matches, "turning-radius", |radius: &f64| *radius > 0.)?;
+    let budget = Budget {
+        max_iterations: if matches.is_present("max-iterations") {
+            Some(parse_param(matches, "max-iterations", |_: &usize| true)?)
+        } else {
+            None
+        },
+        max_nodes: if matches.is_present("max-nodes") {
+            Some(parse_param(matches, "max-nodes", |_: &usize| true)?)
+        } else {
+            None
+        },
+        deadline: if matches.is_present("deadline") {
+            Some(parse_param(matches, "deadline", |deadline: &f64| *deadline >= 0.)?)
+        } else {
+            None
+        },
+    };
+
+    Ok(SolveParams {
+        planner,
+        step,
+        goal_bias,
+        smoothing,
+        spatial_index: !matches.is_present("linear-scan"),
+        seed,
+        robot_radius,
+        boundary,
+        kinematics,
+        turning_radius,
+        lazy: matches.is_present("lazy"),
+        anytime: matches.is_present("anytime"),
+        budget,
+        workers: parse_param(matches, "workers", |workers: &usize| *workers > 0)?,
+    })
+}
+
+fn field_seed(matches: &ArgMatches, params: &SolveParams) -> Result<u64, Error> {
+    if matches.is_present("field-seed") {
+        parse_param(matches, "field-seed", |_: &u64| true)
+    } else {
+        Ok(params.seed)
+    }
+}
+
+fn random_seed() -> u64 {
+    // keep generated seeds short enough to be easily retyped
+    rand::thread_rng().gen::<u32>() as u64
+}
+
+fn load_field(file: &str) -> Result<Field, Error> {
+    field_file::load(file)
+        .map_err(|e| Error::LoadField { file: file.to_string(), error: e, })
+}
+
+fn load_map(matches: &ArgMatches, field: &Field) -> Result<Option<OccupancyGrid>, Error> {
+    if let Some(map_file) = matches.value_of("map") {
+        let threshold = parse_param(matches, "map-threshold", |_: &u8| true)?;
+        map_image::load(map_file, field.config.field_area, threshold)
+            .map(Some)
+            .map_err(|e| Error::LoadMap { file: map_file.to_string(), error: e, })
+    } else {
+        Ok(None)
+    }
+}
+
+fn parse_coords(param: &'static str, value: &str) -> Result<Vec<f64>, Error> {
+    let mut coords = Vec::new();
+    for coord in value.split(',') {
+        coords.push(coord.trim().parse().map_err(|_| Error::InvalidParameter { param, value: value.to_string(), })?);
+    }
+    Ok(coords)
+}
+
+fn parse_obstacle(value: &str) -> Result<Obstacle, Error> {
+    match parse_coords("obstacle", value)?[..] {
+        [x, y, radius] if radius > 0. =>
+            Ok(Obstacle::Circle(CircleArea { center: Point { x, y, }, radius, })),
+        _ =>
+            Err(Error::InvalidParameter { param: "obstacle", value: value.to_string(), }),
+    }
+}
+
+fn parse_rectangle(value: &str) -> Result<Obstacle, Error> {
+    let rectangle = |x, y, width, height, angle: f64| Obstacle::Rectangle(RectangleArea {
+        center: Point { x, y, },
+        width,
+        height,
+        angle: angle.to_radians(),
+    });
+    match parse_coords("rectangle", value)?[..] {
+        [x, y, width, height] if width > 0. && height > 0. =>
+            Ok(rectangle(x, y, width, height, 0.)),
+        [x, y, width, height, angle] if width > 0. && height > 0. =>
+            Ok(rectangle(x, y, width, height, angle)),
+        _ =>
+            Err(Error::InvalidParameter { param: "rectangle", value: value.to_string(), }),
+    }
+}
+
+fn parse_polygon(value: &str) -> Result<Obstacle, Error> {
+    let coords = parse_coords("polygon", value)?;
+    if coords.len() < 6 || coords.len() % 2 != 0 {
+        return Err(Error::InvalidParameter { param: "polygon", value: value.to_string(), });
+    }
+    Ok(Obstacle::Polygon(PolygonArea {
+        vertices: coords.chunks(2).map(|xy| Point { x: xy[0], y: xy[1], }).collect(),
+    }))
+}
 
 fn run() -> Result<(), Error> {
     let matches = app_from_crate!()
@@ -81,10 +445,130 @@ fn run() -> Result<(), Error> {
              .help("Graphics resources directory")
              .default_value("./assets")
              .takes_value(true))
+        .arg(Arg::with_name("field")
+             .short("f")
+             .long("field")
+             .value_name("FILE")
+             .help("Field file to start with, also used by <W> to save and <L> to load the field")
+             .takes_value(true))
+        .args(&solve_params_args())
+        .args(&map_args())
+        .arg(planner_addr_arg())
+        .subcommand(SubCommand::with_name("serve")
+                    .about("Runs the planner alone, serving windows and headless runs started with --planner-addr")
+                    .arg(Arg::with_name("listen")
+                         .short("l")
+                         .long("listen")
+                         .value_name("ADDR")
+                         .help("Address to accept clients at, HOST:PORT or unix:PATH")
+                         .default_value("127.0.0.1:7007")
+                         .takes_value(true)))
+        .subcommand(SubCommand::with_name("solve")
+                    .about("Solves a field without opening a window and prints the route")
+                    .args(&solve_params_args())
+                    .args(&map_args())
+                    .arg(planner_addr_arg())
+                    .arg(Arg::with_name("field")
+                         .short("f")
+                         .long("field")
+                         .value_name("FILE")
+                         .help("Field file to solve (width and height are ignored then)")
+                         .takes_value(true))
+                    .arg(Arg::with_name("width")
+                         .long("width")
+                         .value_name("PIXELS")
+                         .help("Field width")
+                         .default_value("640")
+                         .takes_value(true))
+                    .arg(Arg::with_name("height")
+                         .long("height")
+                         .value_name("PIXELS")
+                         .help("Field height")
+                         .default_value("480")
+                         .takes_value(true))
+                    .arg(Arg::with_name("obstacle")
+                         .short("o")
+                         .long("obstacle")
+                         .value_name("X,Y,RADIUS")
+                         .help("Circle obstacle (may be given multiple times)")
+                         .multiple(true)
+                         .number_of_values(1)
+                         .takes_value(true))
+                    .arg(Arg::with_name("polygon")
+                         .long("polygon")
+                         .value_name("X1,Y1,X2,Y2,X3,Y3,...")
+                         .help("Polygon obstacle with at least three vertices (may be given multiple times)")
+                         .multiple(true)
+                         .number_of_values(1)
+                         .takes_value(true))
+                    .arg(Arg::with_name("rectangle")
+                         .long("rectangle")
+                         .value_name("X,Y,WIDTH,HEIGHT[,ANGLE]")
+                         .help("Rectangle obstacle centered at X,Y and rotated by ANGLE degrees (may be given multiple times)")
+                         .multiple(true)
+                         .number_of_values(1)
+                         .takes_value(true)))
         .get_matches();
 
+    if let Some(serve_matches) = matches.subcommand_matches("serve") {
+        let listen = serve_matches.value_of("listen")
+            .ok_or(Error::MissingParameter("listen"))?;
+        return remote::serve(&remote::Addr::parse(listen))
+            .map_err(Error::Remote);
+    }
+
+    if let Some(solve_matches) = matches.subcommand_matches("solve") {
+        let params = solve_params(solve_matches)?;
+        let mut field = if let Some(field_file) = solve_matches.value_of("field") {
+            load_field(field_file)?
+        } else {
+            let width = parse_param(solve_matches, "width", |width: &f64| *width > 0.)?;
+            let height = parse_param(solve_matches, "height", |height: &f64| *height > CONSOLE_HEIGHT as f64)?;
+            let field_seed = field_seed(solve_matches, &params)?;
+            Field::generate(FieldConfig::new(0., CONSOLE_HEIGHT as f64, width, height), &mut seeded_rng(field_seed))
+        };
+        if let Some(map) = load_map(solve_matches, &field)? {
+            let map = Obstacle::Grid(map);
+            // a loaded field may carry the very same map already
+            if !field.obstacles.contains(&map) {
+                field.obstacles.push(map);
+            }
+        }
+        if let Some(values) = solve_matches.values_of("obstacle") {
+            for value in values {
+                field.obstacles.push(parse_obstacle(value)?);
+            }
+        }
+        if let Some(values) = solve_matches.values_of("polygon") {
+            for value in values {
+                field.obstacles.push(parse_polygon(value)?);
+            }
+        }
+        if let Some(values) = solve_matches.values_of("rectangle") {
+            for value in values {
+                field.obstacles.push(parse_rectangle(value)?);
+            }
+        }
+        return headless::run(field, params, solve_matches.value_of("planner-addr"));
+    }
+
     let assets_dir = matches.value_of("assets-dir")
         .ok_or(Error::MissingParameter("assets-dir"))?;
+    let params = solve_params(&matches)?;
+    let (field, field_seed) = if let Some(field_file) = matches.value_of("field") {
+        (load_field(field_file)?, None)
+    } else {
+        let field_seed = field_seed(&matches, &params)?;
+        let field = Field::generate(FieldConfig::new(
+            0.,
+            CONSOLE_HEIGHT as f64,
+            SCREEN_WIDTH as f64,
+            SCREEN_HEIGHT as f64,
+        ), &mut seeded_rng(field_seed));
+        (field, Some(field_seed))
+    };
+    let map = load_map(&matches, &field)?;
+    let field_path = PathBuf::from(matches.value_of("field").unwrap_or(DEFAULT_FIELD_FILE));
 
     let opengl = OpenGL::V4_1;
     let mut window: PistonWindow = WindowSettings::new("RTT demo", [SCREEN_WIDTH, SCREEN_HEIGHT])
@@ -102,18 +586,13 @@ fn run() -> Result<(), Error> {
             error: e,
         }))?;
 
-    let (master_tx, slave_rx) = mpsc::channel();
-    let (slave_tx, master_rx) = mpsc::channel();
+    let (master_tx, master_rx, slave) = spawn_slave(matches.value_of("planner-addr"))?;
 
-    let slave = thread::Builder::new()
-        .name("RTT demo slave".to_string())
-        .spawn(move || rtt_slave::run(slave_rx, slave_tx))
-        .map_err(Error::ThreadSpawn)?;
-
-    let mut env = Env::new(master_tx, master_rx);
+    let seed_locked = matches.is_present("seed");
+    let mut env = Env::new(master_tx, master_rx, params, seed_locked, field, field_seed, field_path, map);
     while let Some(event) = window.next() {
         let maybe_result = window.draw_2d(&event, |context, g2d| {
-            use piston_window::{clear, text, ellipse, line, Transformed};
+            use piston_window::{clear, text, ellipse, line, polygon, rectangle, Transformed};
             // clear everything
             clear([0.0, 0.0, 0.0, 1.0], g2d);
 
@@ -141,25 +620,68 @@ fn run() -> Result<(), Error> {
                 context.transform,
                 g2d,
             );
+            // draw walls
+            if let Boundary::Walled = env.params.boundary {
+                let (min_x, min_y, max_x, max_y) = env.field.config.field_area;
+                let color = [0.5, 0.5, 0.5, 1.0];
+                line(color, 1., [min_x, min_y, max_x, min_y], context.transform, g2d);
+                line(color, 1., [max_x, min_y, max_x, max_y], context.transform, g2d);
+                line(color, 1., [max_x, max_y, min_x, max_y], context.transform, g2d);
+                line(color, 1., [min_x, max_y, min_x, min_y], context.transform, g2d);
+            }
             // draw obstacles
             for obstacle in env.field.obstacles.iter() {
-                ellipse(
-                    [1.0, 0.5, 0.5, 1.0],
-                    [
-                        obstacle.center.x - obstacle.radius,
-                        obstacle.center.y - obstacle.radius,
-                        obstacle.radius * 2.,
-                        obstacle.radius * 2.,
-                    ],
-                    context.transform,
-                    g2d,
-                );
+                match obstacle {
+                    &Obstacle::Circle(ref circle) =>
+                        ellipse(
+                            [1.0, 0.5, 0.5, 1.0],
+                            [
+                                circle.center.x - circle.radius,
+                                circle.center.y - circle.radius,
+                                circle.radius * 2.,
+                                circle.radius * 2.,
+                            ],
+                            context.transform,
+                            g2d,
+                        ),
+                    &Obstacle::Polygon(ref area) =>
+                        for triangle in area.triangulate() {
+                            polygon(
+                                [1.0, 0.5, 0.5, 1.0],
+                                &[
+                                    [triangle[0].x, triangle[0].y],
+                                    [triangle[1].x, triangle[1].y],
+                                    [triangle[2].x, triangle[2].y],
+                                ],
+                                context.transform,
+                                g2d,
+                            );
+                        },
+                    &Obstacle::Rectangle(ref area) =>
+                        rectangle(
+                            [1.0, 0.5, 0.5, 1.0],
+                            [-area.width / 2., -area.height / 2., area.width, area.height],
+                            context.transform.trans(area.center.x, area.center.y).rot_rad(area.angle),
+                            g2d,
+                        ),
+                    &Obstacle::Grid(..) =>
+                        (),
+                }
+            }
+            for &run in env.grid_runs.iter() {
+                rectangle([1.0, 0.5, 0.5, 1.0], run, context.transform, g2d);
             }
             // draw debug image
             if let Some(ref debug_image) = env.debug_image {
                 for &(ref src, ref dst) in debug_image.routes_segs.iter() {
                     line([0.15, 0.15, 0., 1.0], 1., [src.x, src.y, dst.x, dst.y], context.transform, g2d);
                 }
+                for (worker, segs) in debug_image.workers_segs.iter().enumerate() {
+                    let color = WORKER_COLORS[worker % WORKER_COLORS.len()];
+                    for &(ref src, ref dst) in segs.iter() {
+                        line(color, 1., [src.x, src.y, dst.x, dst.y], context.transform, g2d);
+                    }
+                }
                 match debug_image.sample_seg {
                     SampleTry::None =>
                         (),
@@ -168,17 +690,78 @@ fn run() -> Result<(), Error> {
                     SampleTry::Passable(ref src, ref dst) =>
                         line([0., 1.0, 0., 1.0], 2., [src.x, src.y, dst.x, dst.y], context.transform, g2d),
                 }
+                text::Text::new_color([0.5, 0.5, 0.5, 1.0], 14).draw(
+                    &format!("tick #{}, goal bias {:.0}%", debug_image.tick_id, env.params.goal_bias * 100.),
+                    &mut glyphs,
+                    &context.draw_state,
+                    context.transform.trans(5.0, env.field.config.field_area.3 - 8.0),
+                    g2d
+                ).map_err(PistonError::DrawText)?;
             }
             // draw solved route
             if let Some(ref route) = env.route_solved {
-                let mut route_iter = route.iter().cloned();
-                if let Some(mut src) = route_iter.next() {
-                    for dst in route_iter {
-                        line([0., 1.0, 0., 1.0], 2., [src.x, src.y, dst.x, dst.y], context.transform, g2d);
-                        src = dst;
+                if route.robot_radius > 0. {
+                    let footprint_path = route.smoothed.as_ref().unwrap_or(&route.path);
+                    for center in footprint(footprint_path, route.robot_radius) {
+                        ellipse(
+                            [0., 1.0, 0., 0.1],
+                            [
+                                center.x - route.robot_radius,
+                                center.y - route.robot_radius,
+                                route.robot_radius * 2.,
+                                route.robot_radius * 2.,
+                            ],
+                            context.transform,
+                            g2d,
+                        );
+                    }
+                }
+                for (seg, direction) in route.path.windows(2).zip(route.directions.iter()) {
+                    let color = match direction {
+                        &Direction::Forward =>
+                            [0., 1.0, 0., 1.0],
+                        &Direction::Reverse =>
+                            [1.0, 0.5, 0., 1.0],
+                    };
+                    line(color, 2., [seg[0].x, seg[0].y, seg[1].x, seg[1].y], context.transform, g2d);
+                }
+                // mark cusps where the car switches between driving forward and backward
+                for (i, pair) in route.directions.windows(2).enumerate() {
+                    if pair[0] != pair[1] {
+                        let cusp = &route.path[i + 1];
+                        ellipse([1.0, 1.0, 1.0, 1.0], [cusp.x - 4., cusp.y - 4., 8., 8.], context.transform, g2d);
+                    }
+                }
+                if let Some(ref smoothed) = route.smoothed {
+                    let mut route_iter = smoothed.iter().cloned();
+                    if let Some(mut src) = route_iter.next() {
+                        for dst in route_iter {
+                            line([1.0, 1.0, 0., 1.0], 2., [src.x, src.y, dst.x, dst.y], context.transform, g2d);
+                            src = dst;
+                        }
                     }
                 }
             }
+            // draw polygon being created
+            if let Some(first) = env.obs_vertices.first() {
+                let mut src = first.clone();
+                for dst in env.obs_vertices.iter().skip(1) {
+                    line([1.0, 0., 0., 1.0], 1., [src.x, src.y, dst.x, dst.y], context.transform, g2d);
+                    src = dst.clone();
+                }
+                if let Some((mx, my)) = env.cursor {
+                    line([1.0, 0., 0., 1.0], 1., [src.x, src.y, mx, my], context.transform, g2d);
+                }
+            }
+            // draw rectangle being created
+            if let Some(area) = env.pending_rectangle() {
+                rectangle(
+                    [1.0, 0., 0., 0.5],
+                    [-area.width / 2., -area.height / 2., area.width, area.height],
+                    context.transform.trans(area.center.x, area.center.y).rot_rad(area.angle),
+                    g2d,
+                );
+            }
             // draw cursor
             if let Some((mx, my)) = env.cursor {
                 if let Some((cx, cy)) = env.obs_center {
@@ -200,12 +783,21 @@ fn run() -> Result<(), Error> {
             }
             // draw menu
             text::Text::new_color([0.0, 1.0, 0.0, 1.0], 16).draw(
-                &env.business.info_line(),
+                &env.business.info_line(&env.params, env.seed_locked, env.field_seed),
                 &mut glyphs,
                 &context.draw_state,
                 context.transform.trans(5.0, 20.0),
                 g2d
             ).map_err(PistonError::DrawText)?;
+            for (i, line) in env.console_lines().iter().enumerate() {
+                text::Text::new_color([0.0, 0.6, 0.0, 1.0], 14).draw(
+                    line,
+                    &mut glyphs,
+                    &context.draw_state,
+                    context.transform.trans(5.0, CONSOLE_INFO_HEIGHT as f64 + CONSOLE_LINE_HEIGHT as f64 * (i as f64 + 0.5)),
+                    g2d
+                ).map_err(PistonError::DrawText)?;
+            }
 
 
             Ok(())
@@ -225,12 +817,46 @@ fn run() -> Result<(), Error> {
                 env.solve_debug(),
             Event::Input(Input::Button(ButtonArgs { button: Button::Keyboard(Key::A), state: ButtonState::Release, .. })) =>
                 env.abort(),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Keyboard(Key::P), state: ButtonState::Release, .. })) =>
+                env.switch_planner(),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Keyboard(Key::Z), state: ButtonState::Release, .. })) =>
+                env.switch_lazy(),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Keyboard(Key::I), state: ButtonState::Release, .. })) =>
+                env.switch_anytime(),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Keyboard(Key::LeftBracket), state: ButtonState::Release, .. })) =>
+                env.change_goal_bias(-GOAL_BIAS_STEP),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Keyboard(Key::RightBracket), state: ButtonState::Release, .. })) =>
+                env.change_goal_bias(GOAL_BIAS_STEP),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Keyboard(Key::Minus), state: ButtonState::Release, .. })) =>
+                env.change_robot_radius(-ROBOT_RADIUS_STEP),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Keyboard(Key::Equals), state: ButtonState::Release, .. })) =>
+                env.change_robot_radius(ROBOT_RADIUS_STEP),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Keyboard(Key::V), state: ButtonState::Release, .. })) =>
+                env.switch_kinematics(),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Keyboard(Key::B), state: ButtonState::Release, .. })) =>
+                env.switch_boundary(),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Keyboard(Key::M), state: ButtonState::Release, .. })) =>
+                env.switch_smoothing(),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Keyboard(Key::K), state: ButtonState::Release, .. })) =>
+                env.lock_seed(),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Keyboard(Key::W), state: ButtonState::Release, .. })) =>
+                env.save_field(),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Keyboard(Key::L), state: ButtonState::Release, .. })) =>
+                env.load_field(),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Keyboard(Key::O), state: ButtonState::Release, .. })) =>
+                env.switch_obstacle_tool(),
             Event::Input(Input::Move(Motion::MouseCursor(x, y))) =>
                 env.set_cursor(x, y),
             Event::Input(Input::Cursor(false)) =>
                 env.reset_cursor(),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Mouse(MouseButton::Left), state: ButtonState::Press, .. })) =>
+                env.start_obs_drag(),
             Event::Input(Input::Button(ButtonArgs { button: Button::Mouse(MouseButton::Left), state: ButtonState::Release, .. })) =>
                 env.toggle_obs(),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Mouse(MouseButton::Right), state: ButtonState::Press, .. })) =>
+                env.start_marker_drag(),
+            Event::Input(Input::Button(ButtonArgs { button: Button::Mouse(MouseButton::Right), state: ButtonState::Release, .. })) =>
+                env.drop_marker(),
             Event::Input(Input::Resize(width, height)) =>
                 env.reset(width, height),
             _ =>
@@ -248,6 +874,47 @@ fn run() -> Result<(), Error> {
     Ok(())
 }
 
+enum ObstacleTool {
+    Circle,
+    Polygon,
+    Rectangle,
+    OrientedRectangle,
+}
+
+impl ObstacleTool {
+    fn name(&self) -> &'static str {
+        match self {
+            &ObstacleTool::Circle =>
+                "circle",
+            &ObstacleTool::Polygon =>
+                "polygon",
+            &ObstacleTool::Rectangle =>
+                "rectangle",
+            &ObstacleTool::OrientedRectangle =>
+                "rotated rectangle",
+        }
+    }
+
+    fn next(&self) -> ObstacleTool {
+        match self {
+            &ObstacleTool::Circle =>
+                ObstacleTool::Polygon,
+            &ObstacleTool::Polygon =>
+                ObstacleTool::Rectangle,
+            &ObstacleTool::Rectangle =>
+                ObstacleTool::OrientedRectangle,
+            &ObstacleTool::OrientedRectangle =>
+                ObstacleTool::Circle,
+        }
+    }
+}
+
+// start or finish being dragged with the right mouse button
+enum Marker {
+    Start,
+    Finish,
+}
+
 enum Business {
     Idle,
     Solve,
@@ -255,89 +922,303 @@ enum Business {
 }
 
 impl Business {
-    fn info_line(&self) -> String {
+    // both seeds are shown, so that `--seed` and `--field-seed` reproduce the run
+    fn info_line(&self, params: &SolveParams, seed_locked: bool, field_seed: Option<u64>) -> String {
+        let seeds = match field_seed {
+            Some(field_seed) =>
+                format!("seed {}{}, field seed {}", params.seed, if seed_locked { " locked" } else { "" }, field_seed),
+            None =>
+                format!("seed {}{}", params.seed, if seed_locked { " locked" } else { "" }),
+        };
         match self {
             &Business::Idle =>
-                "<S> to solve, <D> to solve with debug, <C> to clear or <Q> to exit".to_string(),
+                format!(
+                    "[ {}, {} ] <S> solve, <D> debug, <P> planner, <Z> lazy, <I> anytime, <K> lock seed, <C> clear, <Q> exit",
+                    planner_label(params),
+                    seeds,
+                ),
             &Business::Solve =>
-                "[ solving in progress ] <A> to abort, <C> to clear or <Q> to exit".to_string(),
+                format!("[ {} solving, {} ] <A> to abort, <C> to clear or <Q> to exit", planner_label(params), seeds),
             &Business::SolveDebug =>
-                "[ debug solving in progress ] <A> to abort, <C> to clear or <Q> to exit".to_string(),
+                format!("[ {} debug solving, {} ] <A> to abort, <C> to clear or <Q> to exit", planner_label(params), seeds),
         }
     }
 }
 
+// flags are only shown for the planners honouring them
+fn planner_label(params: &SolveParams) -> String {
+    let planner = params.planner_in_use();
+    let mut label = planner.name().to_string();
+    if let (Planner::RttStar, true) = (planner, params.anytime) {
+        label = format!("anytime {}", label);
+    }
+    if let (Planner::Rtt, true) = (planner, params.lazy) {
+        label = format!("lazy {}", label);
+    }
+    if let Planner::Rtt = planner {
+        if params.workers > 1 {
+            label = format!("{} x{}", label, params.workers);
+        }
+    }
+    label
+}
+
 struct Env {
     business: Business,
+    params: SolveParams,
     field: Field,
+    // seed the field has been generated from, `None` for a field loaded from a file which is kept as is
+    field_seed: Option<u64>,
+    field_path: PathBuf,
+    // occupancy grid from the command line, it survives field clearing and window resizing
+    map: Option<OccupancyGrid>,
+    notice: Option<String>,
+    seed_locked: bool,
     cursor: Option<(f64, f64)>,
+    obs_tool: ObstacleTool,
     obs_center: Option<(f64, f64)>,
-    route_solved: Option<Vec<Point>>,
+    obs_vertices: Vec<Point>,
+    // mouse button press position while dragging a rectangle
+    obs_drag: Option<Point>,
+    // first side of a rotated rectangle, waiting for a click which sets its depth
+    obs_side: Option<(Point, Point)>,
+    marker_drag: Option<Marker>,
+    route_solved: Option<Route>,
+    stats: Option<SolveStats>,
     debug_image: Option<DebugImage>,
+    // occupied runs of all the grid obstacles, merging cells is too slow to be done every frame
+    grid_runs: Vec<[f64; 4]>,
     tx: mpsc::Sender<MasterPacket>,
     rx: mpsc::Receiver<SlavePacket>,
 }
 
 impl Env {
-    fn new(tx: mpsc::Sender<MasterPacket>, rx: mpsc::Receiver<SlavePacket>) -> Env {
-        Env {
+    fn new(
+        tx: mpsc::Sender<MasterPacket>,
+        rx: mpsc::Receiver<SlavePacket>,
+        params: SolveParams,
+        seed_locked: bool,
+        field: Field,
+        field_seed: Option<u64>,
+        field_path: PathBuf,
+        map: Option<OccupancyGrid>,
+    )
+        -> Env
+    {
+        let mut env = Env {
             business: Business::Idle,
-            field: Field::generate(FieldConfig::new(
-                0.,
-                CONSOLE_HEIGHT as f64,
-                SCREEN_WIDTH as f64,
-                SCREEN_HEIGHT as f64,
-            )),
+            params,
+            field,
+            field_seed,
+            field_path,
+            map,
+            notice: None,
+            seed_locked,
             cursor: None,
+            obs_tool: ObstacleTool::Circle,
             obs_center: None,
+            obs_vertices: Vec::new(),
+            obs_drag: None,
+            obs_side: None,
+            marker_drag: None,
             debug_image: None,
             route_solved: None,
+            stats: None,
+            grid_runs: Vec::new(),
             tx, rx,
-        }
+        };
+        env.restore_map();
+        env
     }
 
     fn reset(&mut self, width: u32, height: u32) {
+        let (_, _, max_x, max_y) = self.field.config.field_area;
+        if max_x == width as f64 && max_y == height as f64 {
+            // keep the current field when the window size has not actually changed
+            return;
+        }
+        // a loaded field stays as it is, only generated ones follow the window size
+        let field_seed = match self.field_seed {
+            Some(field_seed) =>
+                field_seed,
+            None =>
+                return,
+        };
         self.abort();
+        self.notice = None;
         self.field = Field::generate(FieldConfig::new(
             0.,
             CONSOLE_HEIGHT as f64,
             width as f64,
             height as f64,
-        ));
+        ), &mut seeded_rng(field_seed));
+        self.restore_map();
         self.route_solved = None;
+        self.stats = None;
         self.debug_image = None;
         self.reset_cursor();
     }
 
     fn clear(&mut self) {
         self.abort();
+        self.notice = None;
         self.field.obstacles.clear();
+        self.restore_map();
         self.route_solved = None;
+        self.stats = None;
         self.debug_image = None;
         self.reset_cursor();
     }
 
+    fn restore_map(&mut self) {
+        if let Some(ref map) = self.map {
+            let mut map = map.clone();
+            map.area = self.field.config.field_area;
+            let map = Obstacle::Grid(map);
+            // a loaded field may carry the very same map already
+            if !self.field.obstacles.contains(&map) {
+                self.field.obstacles.push(map);
+            }
+        }
+        self.refresh_grid_runs();
+    }
+
+    fn refresh_grid_runs(&mut self) {
+        self.grid_runs = self.field.obstacles.iter()
+            .flat_map(|obstacle| match obstacle {
+                &Obstacle::Grid(ref grid) =>
+                    grid.occupied_runs(),
+                _ =>
+                    Vec::new(),
+            })
+            .collect();
+    }
+
     fn set_cursor(&mut self, x: f64, y: f64) {
         self.cursor = if y < CONSOLE_HEIGHT as f64 {
             None
         } else {
             Some((x, y))
+        };
+        if let Some((mx, my)) = self.cursor {
+            let to = Point { x: mx, y: my, };
+            match self.marker_drag {
+                Some(Marker::Start) => {
+                    self.field.start = to;
+                    self.field.config.start_area.center = to;
+                },
+                Some(Marker::Finish) =>
+                    self.field.config.finish_area.center = to,
+                None =>
+                    (),
+            }
         }
     }
 
     fn reset_cursor(&mut self) {
         self.cursor = None;
         self.obs_center = None;
+        self.obs_vertices.clear();
+        self.obs_drag = None;
+        self.obs_side = None;
+        self.marker_drag = None;
+    }
+
+    fn start_marker_drag(&mut self) {
+        if let Some((mx, my)) = self.cursor {
+            let cursor = Point { x: mx, y: my, };
+            let grabs = |area: &CircleArea| area.center.sq_dist(&cursor) <= area.radius * area.radius;
+            self.marker_drag = if grabs(&self.field.config.finish_area) {
+                Some(Marker::Finish)
+            } else if grabs(&self.field.config.start_area) {
+                Some(Marker::Start)
+            } else {
+                None
+            };
+            if self.marker_drag.is_some() {
+                self.abort();
+                self.route_solved = None;
+                self.debug_image = None;
+            }
+        }
+    }
+
+    // the PRM roadmap survives moving start and finish, so the route is requeried right away
+    fn drop_marker(&mut self) {
+        if self.marker_drag.take().is_some() {
+            if let Planner::Prm = self.params.planner {
+                self.solve();
+            }
+        }
+    }
+
+    fn start_obs_drag(&mut self) {
+        match self.obs_tool {
+            ObstacleTool::Rectangle | ObstacleTool::OrientedRectangle if self.obs_side.is_none() =>
+                self.obs_drag = self.cursor.map(|(mx, my)| Point { x: mx, y: my, }),
+            _ =>
+                (),
+        }
+    }
+
+    fn pending_rectangle(&self) -> Option<RectangleArea> {
+        let (mx, my) = self.cursor?;
+        let cursor = Point { x: mx, y: my, };
+        match (&self.obs_tool, &self.obs_drag, &self.obs_side) {
+            (&ObstacleTool::Rectangle, &Some(ref start), _) =>
+                Some(RectangleArea::from_corners(start, &cursor)),
+            (&ObstacleTool::OrientedRectangle, &Some(ref start), _) =>
+                Some(RectangleArea::from_side(start, &cursor, &cursor)),
+            (&ObstacleTool::OrientedRectangle, _, &Some((ref a, ref b))) =>
+                Some(RectangleArea::from_side(a, b, &cursor)),
+            _ =>
+                None,
+        }
     }
 
     fn toggle_obs(&mut self) {
+        match self.obs_tool {
+            ObstacleTool::Circle =>
+                self.add_obs_circle(),
+            ObstacleTool::Polygon =>
+                self.add_obs_vertex(),
+            ObstacleTool::Rectangle | ObstacleTool::OrientedRectangle =>
+                self.add_obs_rectangle(),
+        }
+    }
+
+    fn add_obs_rectangle(&mut self) {
+        let area = self.pending_rectangle();
+        let side = match (self.obs_drag.take(), self.cursor) {
+            (Some(start), Some((mx, my))) =>
+                Some((start, Point { x: mx, y: my, })),
+            _ =>
+                None,
+        };
+        match self.obs_tool {
+            ObstacleTool::OrientedRectangle if self.obs_side.is_none() =>
+                // the drag has set the first side, the depth is picked by the next click
+                self.obs_side = side.and_then(|(a, b)| if a.sq_dist(&b) > 0. { Some((a, b)) } else { None }),
+            _ => {
+                self.obs_side = None;
+                if let Some(area) = area {
+                    if area.width > 0. && area.height > 0. {
+                        self.abort();
+                        self.field.obstacles.push(Obstacle::Rectangle(area));
+                    }
+                }
+            },
+        }
+    }
+
+    fn add_obs_circle(&mut self) {
         if let Some((mx, my)) = self.cursor {
             self.obs_center = if let Some((cx, cy)) = self.obs_center {
                 self.abort();
-                self.field.obstacles.push(CircleArea {
+                self.field.obstacles.push(Obstacle::Circle(CircleArea {
                     center: Point { x: cx, y: cy, },
                     radius: coords_radius(cx, cy, mx, my),
-                });
+                }));
                 None
             } else {
                 Some((mx, my))
@@ -345,12 +1226,212 @@ impl Env {
         }
     }
 
+    fn add_obs_vertex(&mut self) {
+        if let Some((mx, my)) = self.cursor {
+            let closes = match self.obs_vertices.first() {
+                Some(first) if self.obs_vertices.len() >= 3 =>
+                    coords_radius(first.x, first.y, mx, my) <= POLYGON_CLOSE_DISTANCE,
+                _ =>
+                    false,
+            };
+            if closes {
+                self.abort();
+                let vertices = self.obs_vertices.drain(..).collect();
+                self.field.obstacles.push(Obstacle::Polygon(PolygonArea { vertices, }));
+            } else {
+                self.obs_vertices.push(Point { x: mx, y: my, });
+            }
+        }
+    }
+
+    fn switch_obstacle_tool(&mut self) {
+        self.obs_tool = self.obs_tool.next();
+        self.obs_center = None;
+        self.obs_vertices.clear();
+        self.obs_drag = None;
+        self.obs_side = None;
+    }
+
+    fn switch_planner(&mut self) {
+        if let Business::Idle = self.business {
+            self.params.planner = self.params.planner.next();
+        }
+    }
+
+    fn switch_lazy(&mut self) {
+        if let Business::Idle = self.business {
+            self.params.lazy = !self.params.lazy;
+        }
+    }
+
+    fn switch_anytime(&mut self) {
+        if let Business::Idle = self.business {
+            self.params.anytime = !self.params.anytime;
+        }
+    }
+
+    fn change_goal_bias(&mut self, delta: f64) {
+        if let Business::Idle = self.business {
+            let goal_bias = self.params.goal_bias + delta;
+            self.params.goal_bias = if goal_bias < 0. {
+                0.
+            } else if goal_bias > 1. {
+                1.
+            } else {
+                goal_bias
+            };
+        }
+    }
+
+    fn change_robot_radius(&mut self, delta: f64) {
+        if let Business::Idle = self.business {
+            let robot_radius = self.params.robot_radius + delta;
+            self.params.robot_radius = if robot_radius < 0. { 0. } else { robot_radius };
+        }
+    }
+
+    fn console_lines(&self) -> [String; CONSOLE_LINES] {
+        [self.params_line(), self.robot_line(), self.stats_line(), self.route_line()]
+    }
+
+    fn params_line(&self) -> String {
+        let step = if let Some(step) = self.params.step {
+            format!("{}", step)
+        } else {
+            "unlimited".to_string()
+        };
+        format!(
+            "step: {}, goal bias: {:.0}% (<[> / <]>), smoothing: {} (<M>), obstacle: {} (<O>)",
+            step,
+            self.params.goal_bias * 100.,
+            self.params.smoothing.name(),
+            self.obs_tool.name(),
+        )
+    }
+
+    fn robot_line(&self) -> String {
+        let kinematics = match self.params.kinematics {
+            Kinematics::Holonomic =>
+                self.params.kinematics.name().to_string(),
+            Kinematics::Dubins | Kinematics::ReedsShepp =>
+                format!("{}, turning radius {}", self.params.kinematics.name(), self.params.turning_radius),
+        };
+        format!(
+            "robot: {} (<V>), radius: {} (<-> / <=>), boundary: {} (<B>)",
+            kinematics,
+            self.params.robot_radius,
+            self.params.boundary.name(),
+        )
+    }
+
+    fn lock_seed(&mut self) {
+        if let Business::Idle = self.business {
+            self.seed_locked = !self.seed_locked;
+        }
+    }
+
+    fn next_seed(&mut self) {
+        if !self.seed_locked {
+            self.params.seed = random_seed();
+        }
+    }
+
+    fn switch_kinematics(&mut self) {
+        if let Business::Idle = self.business {
+            self.params.kinematics = self.params.kinematics.next();
+        }
+    }
+
+    fn switch_boundary(&mut self) {
+        if let Business::Idle = self.business {
+            self.params.boundary = self.params.boundary.next();
+        }
+    }
+
+    fn switch_smoothing(&mut self) {
+        if let Business::Idle = self.business {
+            self.params.smoothing = self.params.smoothing.next();
+        }
+    }
+
+    fn save_field(&mut self) {
+        self.notice = Some(match field_file::save(&self.field_path, &self.field) {
+            Ok(()) =>
+                format!("field saved to {}", self.field_path.display()),
+            Err(e) => {
+                error!("failed to save field to {}: {:?}", self.field_path.display(), e);
+                format!("failed to save field to {}", self.field_path.display())
+            },
+        });
+    }
+
+    fn load_field(&mut self) {
+        self.abort();
+        self.notice = Some(match field_file::load(&self.field_path) {
+            Ok(field) => {
+                self.field = field;
+                self.field_seed = None;
+                self.refresh_grid_runs();
+                self.route_solved = None;
+                self.stats = None;
+                self.debug_image = None;
+                self.reset_cursor();
+                format!("field loaded from {}", self.field_path.display())
+            },
+            Err(e) => {
+                error!("failed to load field from {}: {:?}", self.field_path.display(), e);
+                format!("failed to load field from {}", self.field_path.display())
+            },
+        });
+    }
+
+    fn stats_line(&self) -> String {
+        match self.stats {
+            None =>
+                "no statistics".to_string(),
+            Some(ref stats) => {
+                let route_length = match stats.route_length {
+                    Some(length) =>
+                        format!("{:.1}", length),
+                    None =>
+                        "none yet".to_string(),
+                };
+                format!(
+                    "samples: {} (blocked: {}, passable: {}), nodes: {}, elapsed: {:.2}s, route: {}",
+                    stats.samples,
+                    stats.blocked,
+                    stats.passable,
+                    stats.nodes,
+                    stats.elapsed,
+                    route_length,
+                )
+            },
+        }
+    }
+
+    fn route_line(&self) -> String {
+        if let Some(ref notice) = self.notice {
+            return notice.clone();
+        }
+        match self.route_solved {
+            None =>
+                "no route".to_string(),
+            Some(Route { ref path, smoothed: None, .. }) =>
+                format!("route length: {:.1}", path_length(path)),
+            Some(Route { ref path, smoothed: Some(ref smoothed), .. }) =>
+                format!("route length: {:.1}, smoothed: {:.1}", path_length(path), path_length(smoothed)),
+        }
+    }
+
     fn solve(&mut self) {
         if let Business::Idle = self.business {
             self.tx.send(MasterPacket::Abort).ok();
             self.route_solved = None;
+            self.stats = None;
             self.debug_image = None;
-            if self.tx.send(MasterPacket::Solve(self.field.clone())).is_ok() {
+            self.notice = None;
+            self.next_seed();
+            if self.tx.send(MasterPacket::Solve(self.field.clone(), self.params.clone())).is_ok() {
                 self.business = Business::Solve;
             }
         }
@@ -360,8 +1441,11 @@ impl Env {
         if let Business::Idle = self.business {
             self.tx.send(MasterPacket::Abort).ok();
             self.route_solved = None;
+            self.stats = None;
             self.debug_image = None;
-            if self.tx.send(MasterPacket::SolveDebug(self.field.clone())).is_ok() {
+            self.notice = None;
+            self.next_seed();
+            if self.tx.send(MasterPacket::SolveDebug(self.field.clone(), self.params.clone())).is_ok() {
                 self.business = Business::SolveDebug;
             }
         }
@@ -390,12 +1474,51 @@ impl Env {
                         false
                     },
                 },
+            Ok(SlavePacket::RouteImproved(route)) =>
+                match self.business {
+                    Business::Idle =>
+                        false,
+                    Business::Solve | Business::SolveDebug => {
+                        // the planner goes on, so stay busy until it is aborted
+                        self.route_solved = Some(route);
+                        false
+                    },
+                },
+            Ok(SlavePacket::RouteFailed(failure)) =>
+                match self.business {
+                    Business::Idle =>
+                        false,
+                    Business::Solve | Business::SolveDebug => {
+                        self.notice = Some(format!("no route: {}", failure.description()));
+                        self.business = Business::Idle;
+                        false
+                    },
+                },
+            Ok(SlavePacket::RouteInfeasible(enclosure)) =>
+                match self.business {
+                    Business::Idle =>
+                        false,
+                    Business::Solve | Business::SolveDebug => {
+                        self.notice = Some(format!("no route: {}", enclosure.description()));
+                        self.business = Business::Idle;
+                        false
+                    },
+                },
             Ok(SlavePacket::DebugTick(debug_image)) => {
                 let to_ack = debug_image.tick_id;
                 self.debug_image = Some(debug_image);
                 self.tx.send(MasterPacket::DebugTickAck(to_ack)).ok();
                 false
             },
+            Ok(SlavePacket::Stats(stats)) =>
+                match self.business {
+                    Business::Idle =>
+                        false,
+                    Business::Solve | Business::SolveDebug => {
+                        self.stats = Some(stats);
+                        false
+                    },
+                },
             Err(mpsc::TryRecvError::Empty) =>
                 false,
             Err(mpsc::TryRecvError::Disconnected) =>
@@ -404,6 +1527,24 @@ impl Env {
     }
 }
 
+// robot positions along the path, spaced by the footprint radius
+fn footprint(path: &[Point], radius: f64) -> Vec<Point> {
+    let mut centers = Vec::new();
+    for seg in path.windows(2) {
+        let len = seg[0].sq_dist(&seg[1]).sqrt();
+        let steps = (len / radius).ceil().max(1.) as usize;
+        for i in 0 .. steps {
+            let t = i as f64 / steps as f64;
+            centers.push(Point {
+                x: seg[0].x + (seg[1].x - seg[0].x) * t,
+                y: seg[0].y + (seg[1].y - seg[0].y) * t,
+            });
+        }
+    }
+    centers.extend(path.last().cloned());
+    centers
+}
+
 fn coords_radius(xa: f64, ya: f64, xb: f64, yb: f64) -> f64 {
     ((xb - xa) * (xb - xa) + (yb - ya) * (yb - ya)).sqrt()
 }
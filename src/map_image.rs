@@ -0,0 +1,30 @@
+//! Occupancy grids imported from bitmaps (floor plans and such).
+//!
+//! The image is converted to grayscale and every pixel darker than the threshold becomes an occupied cell.
+//! One pixel maps to one grid cell, the whole grid is stretched over the field area.
+
+use std::path::Path;
+
+use image;
+
+use super::common::OccupancyGrid;
+
+#[derive(Debug)]
+pub enum Error {
+    Open(image::ImageError),
+    Empty,
+}
+
+pub fn load<P>(path: P, area: (f64, f64, f64, f64), threshold: u8) -> Result<OccupancyGrid, Error> where P: AsRef<Path> {
+    let bitmap = image::open(path)
+        .map_err(Error::Open)?
+        .to_luma();
+    let (cols, rows) = bitmap.dimensions();
+    if cols == 0 || rows == 0 {
+        return Err(Error::Empty);
+    }
+    let cells = bitmap.pixels()
+        .map(|pixel| pixel.data[0] < threshold)
+        .collect();
+    Ok(OccupancyGrid::new(area, cols as usize, rows as usize, cells))
+}
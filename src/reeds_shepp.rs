@@ -0,0 +1,484 @@
+use std::f64::consts::PI;
+
+use super::common::{Point, Pose, Direction};
+use super::dubins::{self, Turn};
+use super::dubins::Turn::{Left as L, Right as R, Straight as S};
+
+const ZERO: f64 = 1e-9;
+
+// shortest curve between two poses for a car which drives both forward and backward with the given minimum
+// turning radius, follows "Optimal paths for a car that goes both forwards and backwards" by Reeds and Shepp
+#[derive(Clone, Debug)]
+pub struct ReedsSheppPath {
+    start: Pose,
+    radius: f64,
+    // segments lengths are measured in turning radii, negative for reverse motion
+    segments: Vec<(Turn, f64)>,
+}
+
+impl ReedsSheppPath {
+    pub fn shortest(start: &Pose, end: &Pose, radius: f64) -> ReedsSheppPath {
+        let mut best = Vec::new();
+        candidates(start, end, radius, &mut |word, lengths| {
+            let candidate: Vec<_> = word.iter().cloned().zip(lengths.iter().cloned()).collect();
+            if best.is_empty() || total(&candidate) < total(&best) {
+                best = candidate;
+            }
+        });
+        ReedsSheppPath { start: *start, radius, segments: best, }
+    }
+
+    pub fn length(&self) -> f64 {
+        total(&self.segments) * self.radius
+    }
+
+    pub fn pose_at(&self, distance: f64) -> Pose {
+        let mut left = distance.max(0.) / self.radius;
+        let mut pose = Pose {
+            point: Point { x: 0., y: 0., },
+            heading: self.start.heading,
+        };
+        for &(turn, len) in self.segments.iter() {
+            let step = left.min(len.abs());
+            pose = dubins::advance(&pose, turn, step * len.signum());
+            left -= step;
+            if left <= 0. {
+                break;
+            }
+        }
+        Pose {
+            point: Point {
+                x: self.start.point.x + pose.point.x * self.radius,
+                y: self.start.point.y + pose.point.y * self.radius,
+            },
+            heading: dubins::mod2pi(pose.heading),
+        }
+    }
+
+    // poses along the first `length` of the curve spaced by at most `step` together with the direction the car
+    // arrives to each of them, the start pose is not included while cusps always are
+    pub fn poses(&self, length: f64, step: f64) -> Vec<(Pose, Direction)> {
+        let mut poses = Vec::new();
+        let mut offset = 0.;
+        for &(_, len) in self.segments.iter() {
+            let take = (len.abs() * self.radius).min(length - offset);
+            if take <= ZERO {
+                continue;
+            }
+            let direction = if len < 0. { Direction::Reverse } else { Direction::Forward };
+            let count = (take / step).ceil() as usize;
+            for i in 1 ..= count {
+                poses.push((self.pose_at(offset + take * i as f64 / count as f64), direction));
+            }
+            offset += take;
+        }
+        poses
+    }
+}
+
+fn total(segments: &[(Turn, f64)]) -> f64 {
+    segments.iter().map(|&(_, len)| len.abs()).sum()
+}
+
+// every word reaching `end` from `start` along with its segments lengths, in turning radii
+fn candidates<F>(start: &Pose, end: &Pose, radius: f64, consider: &mut F) where F: FnMut(&[Turn], &[f64]) {
+    let dx = (end.point.x - start.point.x) / radius;
+    let dy = (end.point.y - start.point.y) / radius;
+    let (sin, cos) = start.heading.sin_cos();
+    let x = cos * dx + sin * dy;
+    let y = -sin * dx + cos * dy;
+    let phi = end.heading - start.heading;
+
+    csc(x, y, phi, consider);
+    ccc(x, y, phi, consider);
+    cccc(x, y, phi, consider);
+    ccsc(x, y, phi, consider);
+    ccscc(x, y, phi, consider);
+}
+
+fn wrap_pi(angle: f64) -> f64 {
+    let v = angle % (2. * PI);
+    if v < -PI {
+        v + 2. * PI
+    } else if v > PI {
+        v - 2. * PI
+    } else {
+        v
+    }
+}
+
+fn polar(x: f64, y: f64) -> (f64, f64) {
+    ((x * x + y * y).sqrt(), y.atan2(x))
+}
+
+fn tau_omega(u: f64, v: f64, xi: f64, eta: f64, phi: f64) -> (f64, f64) {
+    let delta = wrap_pi(u - v);
+    let a = u.sin() - delta.sin();
+    let b = u.cos() - delta.cos() - 1.;
+    let t1 = (eta * a - xi * b).atan2(xi * a + eta * b);
+    let t2 = 2. * (delta.cos() - v.cos() - u.cos()) + 3.;
+    let tau = if t2 < 0. { wrap_pi(t1 + PI) } else { wrap_pi(t1) };
+    (tau, wrap_pi(tau - u + v - phi))
+}
+
+// the five families below check every base formula together with its time flipped (`-x, y, -phi`), reflected
+// (`x, -y, -phi`) and both time flipped and reflected (`-x, -y, phi`) variants, see section 8 of the paper
+
+// formula 8.1
+fn lp_sp_lp(x: f64, y: f64, phi: f64) -> Option<(f64, f64, f64)> {
+    let (u, t) = polar(x - phi.sin(), y - 1. + phi.cos());
+    if t >= -ZERO {
+        let v = wrap_pi(phi - t);
+        if v >= -ZERO {
+            return Some((t, u, v));
+        }
+    }
+    None
+}
+
+// formula 8.2
+fn lp_sp_rp(x: f64, y: f64, phi: f64) -> Option<(f64, f64, f64)> {
+    let (u1, t1) = polar(x + phi.sin(), y - 1. - phi.cos());
+    let u1 = u1 * u1;
+    if u1 >= 4. {
+        let u = (u1 - 4.).sqrt();
+        let t = wrap_pi(t1 + 2.0f64.atan2(u));
+        let v = wrap_pi(t - phi);
+        if t >= -ZERO && v >= -ZERO {
+            return Some((t, u, v));
+        }
+    }
+    None
+}
+
+fn csc<F>(x: f64, y: f64, phi: f64, consider: &mut F) where F: FnMut(&[Turn], &[f64]) {
+    if let Some((t, u, v)) = lp_sp_lp(x, y, phi) {
+        consider(&[L, S, L], &[t, u, v]);
+    }
+    if let Some((t, u, v)) = lp_sp_lp(-x, y, -phi) {
+        consider(&[L, S, L], &[-t, -u, -v]);
+    }
+    if let Some((t, u, v)) = lp_sp_lp(x, -y, -phi) {
+        consider(&[R, S, R], &[t, u, v]);
+    }
+    if let Some((t, u, v)) = lp_sp_lp(-x, -y, phi) {
+        consider(&[R, S, R], &[-t, -u, -v]);
+    }
+    if let Some((t, u, v)) = lp_sp_rp(x, y, phi) {
+        consider(&[L, S, R], &[t, u, v]);
+    }
+    if let Some((t, u, v)) = lp_sp_rp(-x, y, -phi) {
+        consider(&[L, S, R], &[-t, -u, -v]);
+    }
+    if let Some((t, u, v)) = lp_sp_rp(x, -y, -phi) {
+        consider(&[R, S, L], &[t, u, v]);
+    }
+    if let Some((t, u, v)) = lp_sp_rp(-x, -y, phi) {
+        consider(&[R, S, L], &[-t, -u, -v]);
+    }
+}
+
+// formulas 8.3 and 8.4 (with the paper typo fixed)
+fn lp_rm_l(x: f64, y: f64, phi: f64) -> Option<(f64, f64, f64)> {
+    let (u1, theta) = polar(x - phi.sin(), y - 1. + phi.cos());
+    if u1 <= 4. {
+        let u = -2. * (0.25 * u1).asin();
+        let t = wrap_pi(theta + 0.5 * u + PI);
+        let v = wrap_pi(phi - t + u);
+        if t >= -ZERO && u <= ZERO {
+            return Some((t, u, v));
+        }
+    }
+    None
+}
+
+fn ccc<F>(x: f64, y: f64, phi: f64, consider: &mut F) where F: FnMut(&[Turn], &[f64]) {
+    if let Some((t, u, v)) = lp_rm_l(x, y, phi) {
+        consider(&[L, R, L], &[t, u, v]);
+    }
+    if let Some((t, u, v)) = lp_rm_l(-x, y, -phi) {
+        consider(&[L, R, L], &[-t, -u, -v]);
+    }
+    if let Some((t, u, v)) = lp_rm_l(x, -y, -phi) {
+        consider(&[R, L, R], &[t, u, v]);
+    }
+    if let Some((t, u, v)) = lp_rm_l(-x, -y, phi) {
+        consider(&[R, L, R], &[-t, -u, -v]);
+    }
+    // backwards
+    let xb = x * phi.cos() + y * phi.sin();
+    let yb = x * phi.sin() - y * phi.cos();
+    if let Some((t, u, v)) = lp_rm_l(xb, yb, phi) {
+        consider(&[L, R, L], &[v, u, t]);
+    }
+    if let Some((t, u, v)) = lp_rm_l(-xb, yb, -phi) {
+        consider(&[L, R, L], &[-v, -u, -t]);
+    }
+    if let Some((t, u, v)) = lp_rm_l(xb, -yb, -phi) {
+        consider(&[R, L, R], &[v, u, t]);
+    }
+    if let Some((t, u, v)) = lp_rm_l(-xb, -yb, phi) {
+        consider(&[R, L, R], &[-v, -u, -t]);
+    }
+}
+
+// formula 8.7
+fn lp_rup_lum_rm(x: f64, y: f64, phi: f64) -> Option<(f64, f64, f64)> {
+    let xi = x + phi.sin();
+    let eta = y - 1. - phi.cos();
+    let rho = 0.25 * (2. + (xi * xi + eta * eta).sqrt());
+    if rho <= 1. {
+        let u = rho.acos();
+        let (t, v) = tau_omega(u, -u, xi, eta, phi);
+        if t >= -ZERO && v <= ZERO {
+            return Some((t, u, v));
+        }
+    }
+    None
+}
+
+// formula 8.8
+fn lp_rum_lum_rp(x: f64, y: f64, phi: f64) -> Option<(f64, f64, f64)> {
+    let xi = x + phi.sin();
+    let eta = y - 1. - phi.cos();
+    let rho = (20. - xi * xi - eta * eta) / 16.;
+    if rho >= 0. && rho <= 1. {
+        let u = -rho.acos();
+        if u >= -0.5 * PI {
+            let (t, v) = tau_omega(u, u, xi, eta, phi);
+            if t >= -ZERO && v >= -ZERO {
+                return Some((t, u, v));
+            }
+        }
+    }
+    None
+}
+
+fn cccc<F>(x: f64, y: f64, phi: f64, consider: &mut F) where F: FnMut(&[Turn], &[f64]) {
+    if let Some((t, u, v)) = lp_rup_lum_rm(x, y, phi) {
+        consider(&[L, R, L, R], &[t, u, -u, v]);
+    }
+    if let Some((t, u, v)) = lp_rup_lum_rm(-x, y, -phi) {
+        consider(&[L, R, L, R], &[-t, -u, u, -v]);
+    }
+    if let Some((t, u, v)) = lp_rup_lum_rm(x, -y, -phi) {
+        consider(&[R, L, R, L], &[t, u, -u, v]);
+    }
+    if let Some((t, u, v)) = lp_rup_lum_rm(-x, -y, phi) {
+        consider(&[R, L, R, L], &[-t, -u, u, -v]);
+    }
+    if let Some((t, u, v)) = lp_rum_lum_rp(x, y, phi) {
+        consider(&[L, R, L, R], &[t, u, u, v]);
+    }
+    if let Some((t, u, v)) = lp_rum_lum_rp(-x, y, -phi) {
+        consider(&[L, R, L, R], &[-t, -u, -u, -v]);
+    }
+    if let Some((t, u, v)) = lp_rum_lum_rp(x, -y, -phi) {
+        consider(&[R, L, R, L], &[t, u, u, v]);
+    }
+    if let Some((t, u, v)) = lp_rum_lum_rp(-x, -y, phi) {
+        consider(&[R, L, R, L], &[-t, -u, -u, -v]);
+    }
+}
+
+// formula 8.9
+fn lp_rm_sm_lm(x: f64, y: f64, phi: f64) -> Option<(f64, f64, f64)> {
+    let (rho, theta) = polar(x - phi.sin(), y - 1. + phi.cos());
+    if rho >= 2. {
+        let r = (rho * rho - 4.).sqrt();
+        let u = 2. - r;
+        let t = wrap_pi(theta + r.atan2(-2.));
+        let v = wrap_pi(phi - 0.5 * PI - t);
+        if t >= -ZERO && u <= ZERO && v <= ZERO {
+            return Some((t, u, v));
+        }
+    }
+    None
+}
+
+// formula 8.10
+fn lp_rm_sm_rm(x: f64, y: f64, phi: f64) -> Option<(f64, f64, f64)> {
+    let xi = x + phi.sin();
+    let eta = y - 1. - phi.cos();
+    let (rho, theta) = polar(-eta, xi);
+    if rho >= 2. {
+        let t = theta;
+        let u = 2. - rho;
+        let v = wrap_pi(t + 0.5 * PI - phi);
+        if t >= -ZERO && u <= ZERO && v <= ZERO {
+            return Some((t, u, v));
+        }
+    }
+    None
+}
+
+fn ccsc<F>(x: f64, y: f64, phi: f64, consider: &mut F) where F: FnMut(&[Turn], &[f64]) {
+    let half = 0.5 * PI;
+    if let Some((t, u, v)) = lp_rm_sm_lm(x, y, phi) {
+        consider(&[L, R, S, L], &[t, -half, u, v]);
+    }
+    if let Some((t, u, v)) = lp_rm_sm_lm(-x, y, -phi) {
+        consider(&[L, R, S, L], &[-t, half, -u, -v]);
+    }
+    if let Some((t, u, v)) = lp_rm_sm_lm(x, -y, -phi) {
+        consider(&[R, L, S, R], &[t, -half, u, v]);
+    }
+    if let Some((t, u, v)) = lp_rm_sm_lm(-x, -y, phi) {
+        consider(&[R, L, S, R], &[-t, half, -u, -v]);
+    }
+    if let Some((t, u, v)) = lp_rm_sm_rm(x, y, phi) {
+        consider(&[L, R, S, R], &[t, -half, u, v]);
+    }
+    if let Some((t, u, v)) = lp_rm_sm_rm(-x, y, -phi) {
+        consider(&[L, R, S, R], &[-t, half, -u, -v]);
+    }
+    if let Some((t, u, v)) = lp_rm_sm_rm(x, -y, -phi) {
+        consider(&[R, L, S, L], &[t, -half, u, v]);
+    }
+    if let Some((t, u, v)) = lp_rm_sm_rm(-x, -y, phi) {
+        consider(&[R, L, S, L], &[-t, half, -u, -v]);
+    }
+    // backwards
+    let xb = x * phi.cos() + y * phi.sin();
+    let yb = x * phi.sin() - y * phi.cos();
+    if let Some((t, u, v)) = lp_rm_sm_lm(xb, yb, phi) {
+        consider(&[L, S, R, L], &[v, u, -half, t]);
+    }
+    if let Some((t, u, v)) = lp_rm_sm_lm(-xb, yb, -phi) {
+        consider(&[L, S, R, L], &[-v, -u, half, -t]);
+    }
+    if let Some((t, u, v)) = lp_rm_sm_lm(xb, -yb, -phi) {
+        consider(&[R, S, L, R], &[v, u, -half, t]);
+    }
+    if let Some((t, u, v)) = lp_rm_sm_lm(-xb, -yb, phi) {
+        consider(&[R, S, L, R], &[-v, -u, half, -t]);
+    }
+    if let Some((t, u, v)) = lp_rm_sm_rm(xb, yb, phi) {
+        consider(&[R, S, R, L], &[v, u, -half, t]);
+    }
+    if let Some((t, u, v)) = lp_rm_sm_rm(-xb, yb, -phi) {
+        consider(&[R, S, R, L], &[-v, -u, half, -t]);
+    }
+    if let Some((t, u, v)) = lp_rm_sm_rm(xb, -yb, -phi) {
+        consider(&[L, S, L, R], &[v, u, -half, t]);
+    }
+    if let Some((t, u, v)) = lp_rm_sm_rm(-xb, -yb, phi) {
+        consider(&[L, S, L, R], &[-v, -u, half, -t]);
+    }
+}
+
+// formula 8.11 (with the paper typo fixed)
+fn lp_rm_s_lm_rp(x: f64, y: f64, phi: f64) -> Option<(f64, f64, f64)> {
+    let xi = x + phi.sin();
+    let eta = y - 1. - phi.cos();
+    let (rho, _) = polar(xi, eta);
+    if rho >= 2. {
+        let u = 4. - (rho * rho - 4.).sqrt();
+        if u <= ZERO {
+            let t = wrap_pi(((4. - u) * xi - 2. * eta).atan2(-2. * xi + (u - 4.) * eta));
+            let v = wrap_pi(t - phi);
+            if t >= -ZERO && v >= -ZERO {
+                return Some((t, u, v));
+            }
+        }
+    }
+    None
+}
+
+fn ccscc<F>(x: f64, y: f64, phi: f64, consider: &mut F) where F: FnMut(&[Turn], &[f64]) {
+    let half = 0.5 * PI;
+    if let Some((t, u, v)) = lp_rm_s_lm_rp(x, y, phi) {
+        consider(&[L, R, S, L, R], &[t, -half, u, -half, v]);
+    }
+    if let Some((t, u, v)) = lp_rm_s_lm_rp(-x, y, -phi) {
+        consider(&[L, R, S, L, R], &[-t, half, -u, half, -v]);
+    }
+    if let Some((t, u, v)) = lp_rm_s_lm_rp(x, -y, -phi) {
+        consider(&[R, L, S, R, L], &[t, -half, u, -half, v]);
+    }
+    if let Some((t, u, v)) = lp_rm_s_lm_rp(-x, -y, phi) {
+        consider(&[R, L, S, R, L], &[-t, half, -u, half, -v]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use rand::Rng;
+
+    use super::{ReedsSheppPath, candidates};
+    use super::super::common::{Point, Pose, Direction, seeded_rng};
+    use super::super::dubins::{DubinsPath, mod2pi};
+
+    const RADIUS: f64 = 20.;
+    const EPSILON: f64 = 1e-6;
+
+    fn random_pose<R>(rng: &mut R) -> Pose where R: Rng {
+        Pose {
+            point: Point { x: rng.gen_range(0., 200.), y: rng.gen_range(0., 200.), },
+            heading: rng.gen_range(0., 2. * PI),
+        }
+    }
+
+    fn assert_reaches(path: &ReedsSheppPath, end: &Pose) {
+        let reached = path.pose_at(path.length());
+        let heading_error = mod2pi(reached.heading - end.heading + PI) - PI;
+        assert!(reached.point.sq_dist(&end.point).sqrt() < EPSILON && heading_error.abs() < EPSILON,
+                "{:?} reached instead of {:?} by {:?}", reached, end, path);
+    }
+
+    #[test]
+    fn every_word_ends_at_the_target_pose() {
+        let mut rng = seeded_rng(7);
+        for _ in 0 .. 500 {
+            let start = random_pose(&mut rng);
+            let end = random_pose(&mut rng);
+            let mut count = 0;
+            candidates(&start, &end, RADIUS, &mut |word, lengths| {
+                let segments = word.iter().cloned().zip(lengths.iter().cloned()).collect();
+                assert_reaches(&ReedsSheppPath { start, radius: RADIUS, segments, }, &end);
+                count += 1;
+            });
+            assert!(count > 0, "no word from {:?} to {:?}", start, end);
+        }
+    }
+
+    // driving forward only is one of the options, so the curve is never longer than the Dubins one
+    #[test]
+    fn shortest_word_is_no_longer_than_any_other_nor_dubins() {
+        let mut rng = seeded_rng(8);
+        for _ in 0 .. 500 {
+            let start = random_pose(&mut rng);
+            let end = random_pose(&mut rng);
+            let path = ReedsSheppPath::shortest(&start, &end, RADIUS);
+            assert_reaches(&path, &end);
+            candidates(&start, &end, RADIUS, &mut |_, lengths| {
+                let length = lengths.iter().map(|len| len.abs()).sum::<f64>() * RADIUS;
+                assert!(path.length() <= length + EPSILON);
+            });
+            assert!(path.length() <= DubinsPath::shortest(&start, &end, RADIUS).length() + EPSILON);
+        }
+    }
+
+    #[test]
+    fn shortest_word_for_known_configurations() {
+        let start = Pose { point: Point { x: 100., y: 100., }, heading: 0., };
+        let pose = |x: f64, y: f64, heading: f64| Pose { point: Point { x: 100. + x, y: 100. + y, }, heading, };
+        // (target relative to the start, expected length, direction the whole curve is driven in)
+        let cases = [
+            (pose(5. * RADIUS, 0., 0.), 5. * RADIUS, Direction::Forward),
+            (pose(-5. * RADIUS, 0., 0.), 5. * RADIUS, Direction::Reverse),
+            (pose(RADIUS, RADIUS, 0.5 * PI), 0.5 * PI * RADIUS, Direction::Forward),
+            (pose(RADIUS, -RADIUS, -0.5 * PI), 0.5 * PI * RADIUS, Direction::Forward),
+            (pose(-RADIUS, RADIUS, -0.5 * PI), 0.5 * PI * RADIUS, Direction::Reverse),
+            (pose(-RADIUS, -RADIUS, 0.5 * PI), 0.5 * PI * RADIUS, Direction::Reverse),
+        ];
+        for &(ref end, length, direction) in cases.iter() {
+            let path = ReedsSheppPath::shortest(&start, end, RADIUS);
+            assert_reaches(&path, end);
+            assert!((path.length() - length).abs() < EPSILON, "{} instead of {} to {:?}", path.length(), length, end);
+            assert!(path.poses(path.length(), 1.).iter().all(|&(_, way)| way == direction), "{:?} to {:?}", path, end);
+        }
+    }
+}
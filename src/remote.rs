@@ -0,0 +1,541 @@
+//! Planner running in another process (`rtt-demo serve`) and reached over a socket.
+//!
+//! Both directions carry the very same packets the planner thread exchanges with the window in process, one JSON
+//! document per line: `MasterPacket` from the client, `SlavePacket` from the server, e.g.
+//!
+//! ```json
+//! {"DebugTickAck":12}
+//! "Abort"
+//! {"RouteFailed":"DeadlineExpired"}
+//! ```
+//!
+//! Addresses are either `HOST:PORT` for TCP or `unix:PATH` for a Unix domain socket. Every accepted connection
+//! gets its own planner, which lives until the client sends `Terminate` or hangs up.
+
+use std::{io, fs, thread};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, Shutdown};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+use std::path::PathBuf;
+use std::sync::mpsc;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+
+use super::{rtt_slave, field_file};
+use super::common::{Field, SolveParams, MasterPacket, SlavePacket, SolveFailure};
+
+#[derive(Debug)]
+pub enum Error {
+    Connect(io::Error),
+    Bind(io::Error),
+    Accept(io::Error),
+    CloneStream(io::Error),
+    ThreadSpawn(io::Error),
+    #[cfg(not(unix))]
+    UnixUnsupported(PathBuf),
+}
+
+#[derive(Clone, Debug)]
+pub enum Addr {
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+impl Addr {
+    pub fn parse(value: &str) -> Addr {
+        if value.starts_with("unix:") {
+            Addr::Unix(PathBuf::from(&value["unix:".len() ..]))
+        } else {
+            Addr::Tcp(value.to_string())
+        }
+    }
+}
+
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Stream {
+    fn try_clone(&self) -> Result<Stream, Error> {
+        match self {
+            &Stream::Tcp(ref stream) =>
+                stream.try_clone().map(Stream::Tcp),
+            #[cfg(unix)]
+            &Stream::Unix(ref stream) =>
+                stream.try_clone().map(Stream::Unix),
+        }.map_err(Error::CloneStream)
+    }
+
+    // wakes up the other half of the stream blocked on reading
+    fn shutdown(&self) {
+        match self {
+            &Stream::Tcp(ref stream) =>
+                stream.shutdown(Shutdown::Both).ok(),
+            #[cfg(unix)]
+            &Stream::Unix(ref stream) =>
+                stream.shutdown(Shutdown::Both).ok(),
+        };
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            &mut Stream::Tcp(ref mut stream) =>
+                stream.read(buf),
+            #[cfg(unix)]
+            &mut Stream::Unix(ref mut stream) =>
+                stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            &mut Stream::Tcp(ref mut stream) =>
+                stream.write(buf),
+            #[cfg(unix)]
+            &mut Stream::Unix(ref mut stream) =>
+                stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            &mut Stream::Tcp(ref mut stream) =>
+                stream.flush(),
+            #[cfg(unix)]
+            &mut Stream::Unix(ref mut stream) =>
+                stream.flush(),
+        }
+    }
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+impl Listener {
+    fn bind(addr: &Addr) -> Result<Listener, Error> {
+        match addr {
+            &Addr::Tcp(ref addr) =>
+                TcpListener::bind(addr.as_str()).map(Listener::Tcp).map_err(Error::Bind),
+            #[cfg(unix)]
+            &Addr::Unix(ref path) => {
+                // a socket file left behind by a previous server would make binding fail
+                let stale = fs::symlink_metadata(path)
+                    .map(|metadata| metadata.file_type().is_socket())
+                    .unwrap_or(false);
+                if stale {
+                    fs::remove_file(path).map_err(Error::Bind)?;
+                }
+                UnixListener::bind(path).map(Listener::Unix).map_err(Error::Bind)
+            },
+            #[cfg(not(unix))]
+            &Addr::Unix(ref path) =>
+                Err(Error::UnixUnsupported(path.clone())),
+        }
+    }
+
+    fn accept(&self) -> Result<Stream, Error> {
+        match self {
+            &Listener::Tcp(ref listener) =>
+                listener.accept().map(|(stream, _)| Stream::Tcp(stream)),
+            #[cfg(unix)]
+            &Listener::Unix(ref listener) =>
+                listener.accept().map(|(stream, _)| Stream::Unix(stream)),
+        }.map_err(Error::Accept)
+    }
+}
+
+fn connect_stream(addr: &Addr) -> Result<Stream, Error> {
+    match addr {
+        &Addr::Tcp(ref addr) => {
+            let stream = TcpStream::connect(addr.as_str()).map_err(Error::Connect)?;
+            // debug ticks and their acks go back and forth one by one
+            stream.set_nodelay(true).ok();
+            Ok(Stream::Tcp(stream))
+        },
+        #[cfg(unix)]
+        &Addr::Unix(ref path) =>
+            UnixStream::connect(path).map(Stream::Unix).map_err(Error::Connect),
+        #[cfg(not(unix))]
+        &Addr::Unix(ref path) =>
+            Err(Error::UnixUnsupported(path.clone())),
+    }
+}
+
+// longest line accepted from a peer, which keeps a misbehaving one from exhausting the memory; the largest packets
+// are debug ticks of big trees, which stay well under that
+const MAX_PACKET_BYTES: u64 = 64 * 1024 * 1024;
+
+fn write_packet<T>(stream: &mut Stream, packet: &T) -> bool where T: Serialize {
+    match serde_json::to_vec(packet) {
+        Ok(mut line) => {
+            line.push(b'\n');
+            stream.write_all(&line).is_ok()
+        },
+        Err(e) => {
+            error!("failed to encode a packet: {}", e);
+            false
+        },
+    }
+}
+
+// `None` once the peer has hung up or sent something unreadable
+fn read_packet<R, T>(reader: &mut R) -> Option<T> where R: BufRead, T: DeserializeOwned {
+    let mut line = String::new();
+    match reader.by_ref().take(MAX_PACKET_BYTES).read_line(&mut line) {
+        Ok(0) | Err(..) =>
+            None,
+        Ok(_) if !line.ends_with('\n') => {
+            error!("packet over {} bytes or cut short, dropping the peer", MAX_PACKET_BYTES);
+            None
+        },
+        Ok(_) =>
+            serde_json::from_str(&line)
+                .map_err(|e| error!("failed to decode a packet: {}", e))
+                .ok(),
+    }
+}
+
+pub struct Connection {
+    stream: Stream,
+    reader: BufReader<Stream>,
+}
+
+// connects to a planner server, the connection is then passed to `run_client` in place of a local planner thread
+pub fn connect(addr: &Addr) -> Result<Connection, Error> {
+    let stream = connect_stream(addr)?;
+    let reader = BufReader::new(stream.try_clone()?);
+    Ok(Connection { stream, reader, })
+}
+
+// relays packets between the window (or headless runner) and the planner server until `Terminate` is sent
+pub fn run_client(connection: Connection, rx: mpsc::Receiver<MasterPacket>, tx: mpsc::Sender<SlavePacket>) -> Result<(), Error> {
+    let Connection { mut stream, mut reader, } = connection;
+    let receiver = thread::Builder::new()
+        .name("RTT demo planner link".to_string())
+        .spawn(move || {
+            while let Some(packet) = read_packet(&mut reader) {
+                if tx.send(packet).is_err() {
+                    break;
+                }
+            }
+        })
+        .map_err(Error::ThreadSpawn)?;
+
+    while let Ok(packet) = rx.recv() {
+        let terminate = if let MasterPacket::Terminate = packet { true } else { false };
+        if !write_packet(&mut stream, &packet) || terminate {
+            break;
+        }
+    }
+    stream.shutdown();
+    receiver.join().ok();
+    Ok(())
+}
+
+// hosts a planner for every client connecting to the address, never returns unless the address can not be served
+pub fn serve(addr: &Addr) -> Result<(), Error> {
+    let listener = Listener::bind(addr)?;
+    info!("serving planner on {:?}", addr);
+    loop {
+        let stream = listener.accept()?;
+        let spawned = thread::Builder::new()
+            .name("RTT demo planner client".to_string())
+            .spawn(move || {
+                if let Err(e) = host(stream) {
+                    error!("planner client failed: {:?}", e);
+                }
+            });
+        if let Err(e) = spawned {
+            error!("failed to spawn a planner client thread: {:?}", e);
+        }
+    }
+}
+
+fn host(mut stream: Stream) -> Result<(), Error> {
+    info!("planner client connected");
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let (master_tx, slave_rx) = mpsc::channel();
+    let (slave_tx, master_rx) = mpsc::channel();
+    let reject_tx = slave_tx.clone();
+
+    let slave = thread::Builder::new()
+        .name("RTT demo slave".to_string())
+        .spawn(move || rtt_slave::run(slave_rx, slave_tx))
+        .map_err(Error::ThreadSpawn)?;
+    // the planner terminates as soon as the client hangs up and `master_tx` is dropped
+    let receiver = thread::Builder::new()
+        .name("RTT demo master link".to_string())
+        .spawn(move || {
+            while let Some(packet) = read_packet::<_, MasterPacket>(&mut reader) {
+                let checked = match &packet {
+                    &MasterPacket::Solve(ref field, ref params) | &MasterPacket::SolveDebug(ref field, ref params) =>
+                        check_request(field, params),
+                    _ =>
+                        Ok(()),
+                };
+                if let Err(reason) = checked {
+                    error!("rejected a solve request: {}", reason);
+                    if reject_tx.send(SlavePacket::RouteFailed(SolveFailure::InvalidRequest)).is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                let terminate = if let MasterPacket::Terminate = packet { true } else { false };
+                if master_tx.send(packet).is_err() || terminate {
+                    break;
+                }
+            }
+        })
+        .map_err(Error::ThreadSpawn)?;
+
+    while let Ok(packet) = master_rx.recv() {
+        if !write_packet(&mut stream, &packet) {
+            break;
+        }
+    }
+    stream.shutdown();
+    receiver.join().ok();
+    slave.join().ok();
+    info!("planner client disconnected");
+    Ok(())
+}
+
+// clients are held to what the command line and field files are, so that no request can take the server down
+fn check_request(field: &Field, params: &SolveParams) -> Result<(), &'static str> {
+    field_file::check(field)?;
+    params.check()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufReader, Cursor, Read};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc;
+    use std::{thread, time};
+
+    use serde::Serialize;
+    use serde_json;
+
+    use super::{Addr, Stream, Error, MAX_PACKET_BYTES, connect, read_packet, run_client, serve, write_packet};
+    use super::super::common::{
+        Field, FieldConfig, Point, CircleArea, PolygonArea, RectangleArea, OccupancyGrid, Obstacle, SolveParams,
+        Planner, Smoothing, Boundary, Kinematics, Budget, MasterPacket, SlavePacket, DebugImage, SampleTry, Route,
+        Direction, SolveStats, SolveFailure, Enclosure,
+    };
+
+    fn field() -> Field {
+        Field {
+            config: FieldConfig::new(0., 0., 400., 300.),
+            start: Point { x: 40.5, y: 40.25, },
+            obstacles: vec![
+                Obstacle::Circle(CircleArea { center: Point { x: 200., y: 150., }, radius: 30., }),
+                Obstacle::Polygon(PolygonArea {
+                    vertices: vec![Point { x: 100., y: 200., }, Point { x: 150., y: 250., }, Point { x: 90., y: 260., }],
+                }),
+                Obstacle::Rectangle(RectangleArea { center: Point { x: 300., y: 80., }, width: 40., height: 20., angle: 0.5, }),
+                Obstacle::Grid(OccupancyGrid::new((0., 0., 400., 300.), 4, 3, (0 .. 12).map(|cell| cell % 5 == 0).collect())),
+            ],
+        }
+    }
+
+    fn params() -> SolveParams {
+        SolveParams {
+            planner: Planner::Rtt,
+            step: Some(10.),
+            goal_bias: 0.25,
+            smoothing: Smoothing::Off,
+            spatial_index: true,
+            seed: 7,
+            robot_radius: 0.,
+            boundary: Boundary::Walled,
+            kinematics: Kinematics::Holonomic,
+            turning_radius: 20.,
+            lazy: false,
+            anytime: false,
+            budget: Budget { max_iterations: Some(100000), max_nodes: None, deadline: Some(10.), },
+            workers: 1,
+        }
+    }
+
+    fn stats() -> SolveStats {
+        SolveStats { samples: 12, blocked: 3, passable: 9, nodes: 10, elapsed: 0.5, route_length: Some(120.75), }
+    }
+
+    fn route() -> Route {
+        let path = vec![Point { x: 40., y: 40., }, Point { x: 80., y: 60.5, }, Point { x: 360., y: 260., }];
+        Route {
+            directions: vec![Direction::Forward, Direction::Reverse],
+            smoothed: Some(path.clone()),
+            path,
+            robot_radius: 4.5,
+            stats: stats(),
+        }
+    }
+
+    fn tcp_pair() -> (Stream, Stream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        (Stream::Tcp(client), Stream::Tcp(server))
+    }
+
+    // packets have no equality, so what arrives is compared in its encoded form
+    fn assert_round_trips<T>(packets: Vec<T>) where T: Serialize + ::serde::de::DeserializeOwned {
+        let (mut src, dst) = tcp_pair();
+        let mut reader = BufReader::new(dst);
+        for packet in packets.iter() {
+            assert!(write_packet(&mut src, packet));
+        }
+        for packet in packets.iter() {
+            let sent = serde_json::to_string(packet).unwrap();
+            let received = read_packet::<_, T>(&mut reader).map(|packet| serde_json::to_string(&packet).unwrap());
+            assert_eq!(received, Some(sent));
+        }
+    }
+
+    #[test]
+    fn master_packets_round_trip() {
+        assert_round_trips(vec![
+            MasterPacket::Solve(field(), params()),
+            MasterPacket::SolveDebug(field(), SolveParams {
+                kinematics: Kinematics::ReedsShepp,
+                budget: Budget::default(),
+                ..params()
+            }),
+            MasterPacket::DebugTickAck(12),
+            MasterPacket::Abort,
+            MasterPacket::Terminate,
+        ]);
+    }
+
+    #[test]
+    fn slave_packets_round_trip() {
+        let seg = (Point { x: 1., y: 2., }, Point { x: 3., y: 4.5, });
+        assert_round_trips(vec![
+            SlavePacket::RouteDone(route()),
+            SlavePacket::RouteImproved(Route { smoothed: None, ..route() }),
+            SlavePacket::RouteFailed(SolveFailure::IterationsExhausted),
+            SlavePacket::RouteFailed(SolveFailure::NodesExhausted),
+            SlavePacket::RouteFailed(SolveFailure::DeadlineExpired),
+            SlavePacket::RouteFailed(SolveFailure::RobotTooLarge),
+            SlavePacket::RouteFailed(SolveFailure::InvalidRequest),
+            SlavePacket::RouteInfeasible(Enclosure::Start),
+            SlavePacket::RouteInfeasible(Enclosure::Goal),
+            SlavePacket::DebugTick(DebugImage {
+                tick_id: 1,
+                routes_segs: vec![seg],
+                sample_seg: SampleTry::None,
+                workers_segs: Vec::new(),
+            }),
+            SlavePacket::DebugTick(DebugImage {
+                tick_id: 2,
+                routes_segs: Vec::new(),
+                sample_seg: SampleTry::Blocked(seg.0, seg.1),
+                workers_segs: vec![vec![seg], Vec::new()],
+            }),
+            SlavePacket::DebugTick(DebugImage {
+                tick_id: 3,
+                routes_segs: vec![seg, seg],
+                sample_seg: SampleTry::Passable(seg.1, seg.0),
+                workers_segs: Vec::new(),
+            }),
+            SlavePacket::Stats(stats()),
+        ]);
+    }
+
+    #[test]
+    fn overlong_line_drops_the_peer() {
+        let input = Cursor::new(b"\"Abort\"\n".to_vec()).chain(io::repeat(b' ').take(MAX_PACKET_BYTES + 1));
+        let mut reader = BufReader::new(input);
+        match read_packet(&mut reader) {
+            Some(MasterPacket::Abort) =>
+                (),
+            _ =>
+                panic!("the packet before the overlong line is lost"),
+        }
+        assert!(read_packet::<_, MasterPacket>(&mut reader).is_none());
+    }
+
+    // starts a server on a free port and connects a client to it
+    fn loopback() -> (mpsc::Sender<MasterPacket>, mpsc::Receiver<SlavePacket>, thread::JoinHandle<Result<(), Error>>) {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let addr = Addr::parse(&format!("127.0.0.1:{}", port));
+        let server_addr = addr.clone();
+        // the server never returns and goes down along with the test process
+        thread::spawn(move || serve(&server_addr));
+
+        let mut connection = None;
+        for _ in 0 .. 100 {
+            if let Ok(connected) = connect(&addr) {
+                connection = Some(connected);
+                break;
+            }
+            thread::sleep(time::Duration::from_millis(50));
+        }
+        let (master_tx, slave_rx) = mpsc::channel();
+        let (slave_tx, master_rx) = mpsc::channel();
+        let client = thread::spawn(move || run_client(connection.expect("server is not up"), slave_rx, slave_tx));
+        (master_tx, master_rx, client)
+    }
+
+    #[test]
+    fn solves_over_loopback() {
+        let (master_tx, master_rx, client) = loopback();
+        let field = Field { obstacles: Vec::new(), ..field() };
+        let finish = field.config.finish_area;
+        master_tx.send(MasterPacket::Solve(field, params())).unwrap();
+        loop {
+            match master_rx.recv().unwrap() {
+                SlavePacket::RouteDone(route) => {
+                    let end = route.path.last().unwrap();
+                    assert!(end.sq_dist(&finish.center) < finish.radius * finish.radius);
+                    break;
+                },
+                SlavePacket::Stats(..) | SlavePacket::DebugTick(..) =>
+                    (),
+                SlavePacket::RouteImproved(..) | SlavePacket::RouteFailed(..) | SlavePacket::RouteInfeasible(..) =>
+                    panic!("no route over the loopback"),
+            }
+        }
+        master_tx.send(MasterPacket::Terminate).unwrap();
+        assert!(client.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn invalid_requests_are_refused() {
+        let (master_tx, master_rx, client) = loopback();
+        let requests = vec![
+            MasterPacket::Solve(Field { config: FieldConfig::new(0., 0., 0., 300.), ..field() }, params()),
+            MasterPacket::Solve(Field { config: FieldConfig::new(0., 0., 1e9, 1e9), ..field() }, params()),
+            MasterPacket::Solve(field(), SolveParams { step: Some(0.), ..params() }),
+            MasterPacket::SolveDebug(field(), SolveParams { robot_radius: -1., ..params() }),
+            MasterPacket::Solve(field(), SolveParams { turning_radius: 0., ..params() }),
+            MasterPacket::Solve(field(), SolveParams { workers: 0, ..params() }),
+        ];
+        for request in requests {
+            master_tx.send(request).unwrap();
+            match master_rx.recv().unwrap() {
+                SlavePacket::RouteFailed(SolveFailure::InvalidRequest) =>
+                    (),
+                _ =>
+                    panic!("an invalid request is not refused"),
+            }
+        }
+        master_tx.send(MasterPacket::Terminate).unwrap();
+        assert!(client.join().unwrap().is_ok());
+    }
+}
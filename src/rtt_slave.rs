@@ -1,31 +1,55 @@
-use std::sync::mpsc;
-use std::collections::HashSet;
+use std::{mem, sync::mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+use std::cmp::Ordering;
+use std::collections::{HashSet, BinaryHeap};
 
-use rtt::{self, util::{NeverError, rtt::vec_slist::{EmptyRandomTree, RandomTree, NodeRef}}};
-use rand::{self, Rng};
+use rtt::{self, util::{rtt::vec_slist::{EmptyRandomTree, RandomTree, NodeRef}}};
+use rand::{Rng, ChaChaRng};
 
 use super::common::{
     MasterPacket,
     SlavePacket,
+    SolveParams,
+    Budget,
+    SolveFailure,
+    Boundary,
+    Kinematics,
+    Planner,
+    Smoothing,
+    Route,
+    SolveStats,
+    seeded_rng,
+    path_length,
     Field,
+    Obstacle,
     Point,
+    Pose,
+    Direction,
     DebugImage,
     SampleTry,
 };
+use super::spatial::NodesIndex;
+use super::state_space::{StateSpace, MOTION_RESOLUTION};
+use super::feasibility;
+use super::dubins::DubinsPath;
+use super::reeds_shepp::ReedsSheppPath;
 
 pub fn run(rx: mpsc::Receiver<MasterPacket>, tx: mpsc::Sender<SlavePacket>) {
     run_idle(&rx, &tx);
 }
 
 fn run_idle(rx: &mpsc::Receiver<MasterPacket>, tx: &mpsc::Sender<SlavePacket>) {
+    let mut roadmap = None;
+    let mut verdict = None;
     loop {
         match rx.recv() {
-            Ok(MasterPacket::Solve(field)) =>
-                if run_solve(rx, tx, field, false) {
+            Ok(MasterPacket::Solve(field, params)) =>
+                if run_planner(rx, tx, field, params, &mut roadmap, &mut verdict, false) {
                     break;
                 },
-            Ok(MasterPacket::SolveDebug(field)) =>
-                if run_solve(rx, tx, field, true) {
+            Ok(MasterPacket::SolveDebug(field, params)) =>
+                if run_planner(rx, tx, field, params, &mut roadmap, &mut verdict, true) {
                     break;
                 },
             Ok(MasterPacket::DebugTickAck(..)) =>
@@ -40,13 +64,157 @@ fn run_idle(rx: &mpsc::Receiver<MasterPacket>, tx: &mpsc::Sender<SlavePacket>) {
     }
 }
 
+fn run_planner(
+    rx: &mpsc::Receiver<MasterPacket>,
+    tx: &mpsc::Sender<SlavePacket>,
+    field: Field,
+    params: SolveParams,
+    roadmap: &mut Option<Roadmap>,
+    verdict: &mut Option<feasibility::Verdict>,
+    debug: bool,
+)
+    -> bool
+{
+    let trans = Trans::new(field, params);
+    if !trans.robot_fits() {
+        tx.send(SlavePacket::RouteFailed(SolveFailure::RobotTooLarge)).ok();
+        return false;
+    }
+    // no kinematics gets through where even a holonomic robot does not, so one check suits every planner
+    let (free_area, clearance) = (trans.free_area(), trans.params.robot_radius);
+    let reusable = match verdict {
+        &mut Some(ref verdict) =>
+            verdict.fits(&trans.field, free_area, clearance),
+        &mut None =>
+            false,
+    };
+    if !reusable {
+        *verdict = Some(feasibility::Verdict::new(&trans.field, free_area, clearance));
+    }
+    if let Some(enclosure) = verdict.as_ref().unwrap().enclosure {
+        tx.send(SlavePacket::RouteInfeasible(enclosure)).ok();
+        return false;
+    }
+    match (trans.params.kinematics, trans.params.planner) {
+        // only plain RTT grows its tree in a `StateSpace`, so car-like robots are always planned with it: rewiring,
+        // trees connecting and roadmaps all assume straight edges between points
+        (Kinematics::Dubins, _) | (Kinematics::ReedsShepp, _) | (Kinematics::Holonomic, Planner::Rtt) if trans.params.workers > 1 =>
+            run_solve_workers(rx, tx, trans, debug),
+        (Kinematics::Dubins, _) | (Kinematics::ReedsShepp, _) | (Kinematics::Holonomic, Planner::Rtt) =>
+            run_rtt(rx, tx, &trans, debug),
+        (Kinematics::Holonomic, Planner::RttStar) =>
+            run_solve_star(rx, tx, trans, debug),
+        (Kinematics::Holonomic, Planner::RttConnect) =>
+            run_solve_connect(rx, tx, trans, debug),
+        (Kinematics::Holonomic, Planner::Prm) =>
+            run_solve_prm(rx, tx, trans, roadmap, debug),
+    }
+}
+
+fn run_rtt(rx: &mpsc::Receiver<MasterPacket>, tx: &mpsc::Sender<SlavePacket>, trans: &Trans, debug: bool) -> bool {
+    match trans.params.kinematics {
+        Kinematics::Dubins | Kinematics::ReedsShepp if trans.params.lazy =>
+            run_solve_lazy(rx, tx, trans, &PoseSpace { trans, }, debug),
+        Kinematics::Dubins | Kinematics::ReedsShepp =>
+            run_solve(rx, tx, trans, &PoseSpace { trans, }, debug),
+        Kinematics::Holonomic if trans.params.lazy =>
+            run_solve_lazy(rx, tx, trans, &PointSpace { trans, }, debug),
+        Kinematics::Holonomic =>
+            run_solve(rx, tx, trans, &PointSpace { trans, }, debug),
+    }
+}
+
 struct Trans {
     field: Field,
+    params: SolveParams,
 }
 
 impl Trans {
-    fn new(field: Field) -> Trans {
-        Trans { field, }
+    fn new(field: Field, params: SolveParams) -> Trans {
+        Trans { field, params, }
+    }
+
+    fn sample<R>(&self, rng: &mut R) -> Point where R: Rng {
+        if let Some(point) = self.sample_goal(rng) {
+            point
+        } else {
+            let (min_x, min_y, max_x, max_y) = self.free_area();
+            Point {
+                x: rng.gen_range(min_x, max_x),
+                y: rng.gen_range(min_y, max_y),
+            }
+        }
+    }
+
+    // a point inside the finish area with the goal bias probability
+    fn sample_goal<R>(&self, rng: &mut R) -> Option<Point> where R: Rng {
+        if self.params.goal_bias > 0. && rng.gen::<f64>() < self.params.goal_bias {
+            let fp = &self.field.config.finish_area;
+            let radius = fp.radius * rng.gen::<f64>().sqrt();
+            let angle = rng.gen_range(0., ::std::f64::consts::PI * 2.);
+            Some(Point {
+                x: fp.center.x + radius * angle.cos(),
+                y: fp.center.y + radius * angle.sin(),
+            })
+        } else {
+            None
+        }
+    }
+
+    // uniform sample inside the prolate ellipse with foci at the start and the finish center, which holds every
+    // route shorter than `best_cost`; the finish radius is added because routes end anywhere in the finish area.
+    // The goal bias still applies, so a biased draw lands in the finish area instead
+    fn sample_informed<R>(&self, rng: &mut R, best_cost: f64) -> Point where R: Rng {
+        if let Some(point) = self.sample_goal(rng) {
+            return point;
+        }
+        let start = &self.field.start;
+        let finish = &self.field.config.finish_area;
+        let c_min = start.sq_dist(&finish.center).sqrt();
+        let c_max = best_cost + finish.radius;
+        let major = c_max / 2.;
+        let minor = (c_max * c_max - c_min * c_min).max(0.).sqrt() / 2.;
+        let (sin, cos) = (finish.center.y - start.y).atan2(finish.center.x - start.x).sin_cos();
+        let center = Point { x: (start.x + finish.center.x) / 2., y: (start.y + finish.center.y) / 2., };
+        let (min_x, min_y, max_x, max_y) = self.free_area();
+        loop {
+            let radius = rng.gen::<f64>().sqrt();
+            let angle = rng.gen_range(0., ::std::f64::consts::PI * 2.);
+            let (x, y) = (major * radius * angle.cos(), minor * radius * angle.sin());
+            let point = Point {
+                x: center.x + x * cos - y * sin,
+                y: center.y + x * sin + y * cos,
+            };
+            if point.x >= min_x && point.x <= max_x && point.y >= min_y && point.y <= max_y {
+                return point;
+            }
+        }
+    }
+
+    // part of the field the robot center may occupy, empty when the robot does not fit (see `robot_fits`)
+    fn free_area(&self) -> (f64, f64, f64, f64) {
+        let (min_x, min_y, max_x, max_y) = self.field.config.field_area;
+        match self.params.boundary {
+            Boundary::Walled => {
+                let clearance = self.params.robot_radius;
+                (min_x + clearance, min_y + clearance, max_x - clearance, max_y - clearance)
+            },
+            Boundary::Open =>
+                (min_x, min_y, max_x, max_y),
+        }
+    }
+
+    fn robot_fits(&self) -> bool {
+        let (min_x, min_y, max_x, max_y) = self.free_area();
+        min_x < max_x && min_y < max_y
+    }
+
+    fn steer(&self, src: &Point, sample: &Point) -> Point {
+        if let Some(step) = self.params.step {
+            src.steer(sample, step)
+        } else {
+            sample.clone()
+        }
     }
 
     fn goal_reached(&self, point: &Point) -> bool {
@@ -54,41 +222,48 @@ impl Trans {
         fp.center.sq_dist(point) < fp.radius * fp.radius
     }
 
-    fn trans_add_root(&mut self, empty_rtt: EmptyRandomTree<Point>) -> Result<RandomTree<Point>, NeverError> {
-        Ok(empty_rtt.add_root(self.field.start))
+    // car starts facing the finish area
+    fn start_pose(&self) -> Pose {
+        let start = &self.field.start;
+        let finish = &self.field.config.finish_area.center;
+        Pose {
+            point: start.clone(),
+            heading: (finish.y - start.y).atan2(finish.x - start.x),
+        }
+    }
+
+    fn car_curve(&self, src: &Pose, dst: &Pose) -> CarCurve {
+        match self.params.kinematics {
+            Kinematics::ReedsShepp =>
+                CarCurve::ReedsShepp(ReedsSheppPath::shortest(src, dst, self.params.turning_radius)),
+            Kinematics::Holonomic | Kinematics::Dubins =>
+                CarCurve::Dubins(DubinsPath::shortest(src, dst, self.params.turning_radius)),
+        }
+    }
+
+    // with an open boundary the footprint may hang over the border, but the robot center still stays on the field,
+    // which keeps car arcs from swinging out into the console strip
+    fn within_walls(&self, point: &Point) -> bool {
+        let (min_x, min_y, max_x, max_y) = self.free_area();
+        point.x >= min_x && point.x <= max_x && point.y >= min_y && point.y <= max_y
     }
 
-    fn trans_root_node(&mut self, rtt: &mut RandomTree<Point>) -> Result<RttNodeFocus, NeverError> {
-        let root_ref = rtt.root();
-        Ok(RttNodeFocus {
-            node_ref: root_ref,
-            goal_reached: self.goal_reached(&self.field.start),
-        })
+    fn is_free(&self, point: &Point) -> bool {
+        let clearance = self.params.robot_radius;
+        self.within_walls(point) && !self.field.obstacles.iter().any(|obstacle| obstacle.intersects_segment(point, point, clearance))
     }
 
-    fn has_route(&self, rtt: &RandomTree<Point>, node_ref: &NodeRef, dst: &Point) -> bool {
-        let src = rtt.get_state(node_ref);
+    fn has_route(&self, src: &Point, dst: &Point) -> bool {
         if src.sq_dist(dst) <= 0. {
             return false;
         }
-        let seg_v = Point { x: dst.x - src.x, y: dst.y - src.y, };
-        let seg_v_len = (seg_v.x * seg_v.x + seg_v.y * seg_v.y).sqrt();
-
+        // the field is convex, so a segment stays within the walls when both of its ends do
+        if !self.within_walls(src) || !self.within_walls(dst) {
+            return false;
+        }
+        let clearance = self.params.robot_radius;
         for obstacle in self.field.obstacles.iter() {
-            let closest_point = {
-                let pt_v = Point { x: obstacle.center.x - src.x, y: obstacle.center.y - src.y, };
-                let seg_v_unit = Point { x: seg_v.x / seg_v_len, y: seg_v.y / seg_v_len, };
-                let proj = pt_v.x * seg_v_unit.x + pt_v.y * seg_v_unit.y;
-                if proj <= 0. {
-                    src.clone()
-                } else if proj >= seg_v_len {
-                    dst.clone()
-                } else {
-                    let proj_v = Point { x: seg_v_unit.x * proj, y: seg_v_unit.y * proj, };
-                    Point { x: proj_v.x + src.x, y: proj_v.y + src.y, }
-                }
-            };
-            if closest_point.sq_dist(&obstacle.center) < obstacle.radius * obstacle.radius {
+            if obstacle.intersects_segment(src, dst, clearance) {
                 return false;
             }
         }
@@ -97,130 +272,1559 @@ impl Trans {
     }
 }
 
-struct RttNodeFocus {
-    node_ref: NodeRef,
-    goal_reached: bool,
+// holonomic robot moving along straight segments
+struct PointSpace<'a> {
+    trans: &'a Trans,
 }
 
-impl RttNodeFocus {
-    fn into_direct_path(self, rtt: RandomTree<Point>) -> Result<Vec<Point>, NeverError> {
-        let mut rev_path: Vec<_> = rtt.into_path(self.node_ref).collect();
-        rev_path.reverse();
-        Ok(rev_path)
+impl<'a> StateSpace for PointSpace<'a> {
+    type State = Point;
+
+    fn start(&self) -> Point {
+        self.trans.field.start
+    }
+
+    fn sample<R>(&self, rng: &mut R) -> Point where R: Rng {
+        self.trans.sample(rng)
+    }
+
+    fn distance(&self, src: &Point, dst: &Point) -> f64 {
+        src.sq_dist(dst).sqrt()
+    }
+
+    fn interpolate(&self, src: &Point, dst: &Point, t: f64) -> Point {
+        Point {
+            x: src.x + (dst.x - src.x) * t,
+            y: src.y + (dst.y - src.y) * t,
+        }
+    }
+
+    fn is_goal(&self, state: &Point) -> bool {
+        self.trans.goal_reached(state)
+    }
+
+    fn position(&self, state: &Point) -> Point {
+        *state
+    }
+
+    fn nearest_candidates(&self) -> Option<usize> {
+        Some(1)
+    }
+
+    fn motion(&self, _src: &Point, dst: &Point) -> Vec<(Point, Direction)> {
+        vec![(*dst, Direction::Forward)]
+    }
+
+    fn is_valid_motion(&self, src: &Point, dst: &Point) -> bool {
+        self.trans.has_route(src, dst)
+    }
+}
+
+// car-like robot following Dubins or Reeds-Shepp curves
+struct PoseSpace<'a> {
+    trans: &'a Trans,
+}
+
+impl<'a> StateSpace for PoseSpace<'a> {
+    type State = Pose;
+
+    fn start(&self) -> Pose {
+        self.trans.start_pose()
+    }
+
+    fn sample<R>(&self, rng: &mut R) -> Pose where R: Rng {
+        let point = self.trans.sample(rng);
+        Pose { point, heading: rng.gen_range(0., ::std::f64::consts::PI * 2.), }
+    }
+
+    fn distance(&self, src: &Pose, dst: &Pose) -> f64 {
+        self.trans.car_curve(src, dst).length()
+    }
+
+    fn interpolate(&self, src: &Pose, dst: &Pose, t: f64) -> Pose {
+        let curve = self.trans.car_curve(src, dst);
+        curve.pose_at(curve.length() * t)
+    }
+
+    fn is_goal(&self, state: &Pose) -> bool {
+        self.trans.goal_reached(&state.point)
+    }
+
+    fn position(&self, state: &Pose) -> Point {
+        state.point
+    }
+
+    fn nearest_candidates(&self) -> Option<usize> {
+        Some(CAR_NEAREST_CANDIDATES)
+    }
+
+    fn steer(&self, src: &Pose, sample: &Pose, step: Option<f64>) -> Option<Pose> {
+        let curve = self.trans.car_curve(src, sample);
+        let length = match step {
+            Some(step) if step < curve.length() =>
+                step,
+            _ =>
+                curve.length(),
+        };
+        if length <= 0. {
+            None
+        } else {
+            Some(curve.pose_at(length))
+        }
+    }
+
+    fn motion(&self, src: &Pose, dst: &Pose) -> Vec<(Pose, Direction)> {
+        let curve = self.trans.car_curve(src, dst);
+        curve.poses(curve.length(), MOTION_RESOLUTION)
+    }
+
+    // the curve is checked as a polyline so that thin obstacles can not slip between two checked poses
+    fn is_valid_motion(&self, src: &Pose, dst: &Pose) -> bool {
+        let motion = self.motion(src, dst);
+        let mut prev = src.point;
+        for &(ref pose, _) in motion.iter() {
+            if !self.trans.has_route(&prev, &pose.point) {
+                return false;
+            }
+            prev = pose.point;
+        }
+        !motion.is_empty()
+    }
+}
+
+// tree nodes looked up by `StateSpace::distance` to a state
+enum NearestIndex<T> {
+    // first narrowed down to the given number of the closest ones by position
+    Planar(NodesIndex<T>, usize),
+    Scan(Vec<T>),
+}
+
+impl<T> NearestIndex<T> where T: Clone {
+    fn new<S>(space: &S, trans: &Trans) -> NearestIndex<T> where S: StateSpace {
+        match space.nearest_candidates() {
+            Some(candidates) =>
+                NearestIndex::Planar(NodesIndex::new(trans.field.config.field_area, trans.params.spatial_index), candidates),
+            None =>
+                NearestIndex::Scan(Vec::new()),
+        }
+    }
+
+    fn insert(&mut self, point: Point, item: T) {
+        match self {
+            &mut NearestIndex::Planar(ref mut index, _) =>
+                index.insert(point, item),
+            &mut NearestIndex::Scan(ref mut items) =>
+                items.push(item),
+        }
     }
+
+    fn clear(&mut self) {
+        match self {
+            &mut NearestIndex::Planar(ref mut index, _) =>
+                index.clear(),
+            &mut NearestIndex::Scan(ref mut items) =>
+                items.clear(),
+        }
+    }
+
+    // the item `keep` accepts with the least `distance`, `point` being the position of the state it is measured to
+    fn nearest<D, K>(&self, point: &Point, distance: D, keep: K) -> Option<T> where D: Fn(&T) -> f64, K: Fn(&T) -> bool {
+        let by_distance = |a: &T, b: &T| distance(a).partial_cmp(&distance(b)).unwrap();
+        match self {
+            &NearestIndex::Planar(ref index, candidates) =>
+                index.k_nearest_kept(point, candidates, keep).into_iter().min_by(|a, b| by_distance(a, b)),
+            &NearestIndex::Scan(ref items) =>
+                items.iter().filter(|&item| keep(item)).min_by(|a, b| by_distance(a, b)).cloned(),
+        }
+    }
+}
+
+struct RttNodeFocus {
+    node_ref: NodeRef,
+    goal_reached: bool,
 }
 
-fn run_solve(rx: &mpsc::Receiver<MasterPacket>, tx: &mpsc::Sender<SlavePacket>, field: Field, debug: bool) -> bool {
-    let mut rng = rand::thread_rng();
+fn run_solve<S>(rx: &mpsc::Receiver<MasterPacket>, tx: &mpsc::Sender<SlavePacket>, trans: &Trans, space: &S, debug: bool) -> bool where S: StateSpace {
+    let mut rng = seeded_rng(trans.params.seed);
     let mut debug_image = DebugImage {
         tick_id: 0,
         routes_segs: Vec::new(),
         sample_seg: SampleTry::None,
+        workers_segs: Vec::new(),
     };
     let mut last_ack = 0;
-    let mut trans = Trans::new(field);
 
+    let mut index = NearestIndex::new(space, trans);
+
+    let root = space.start();
+    let root_point = space.position(&root);
+    let root_goal = space.is_goal(&root);
     let planner = rtt::PlannerInit::new(EmptyRandomTree::new());
-    let planner = planner.add_root_ok(|empty_rtt| trans.trans_add_root(empty_rtt));
-    let mut planner_node = planner.root_node_ok(|rtt: &mut _| trans.trans_root_node(rtt));
+    let planner = planner.add_root_ok(|empty_rtt: EmptyRandomTree<S::State>| Ok(empty_rtt.add_root(root)));
+    let mut planner_node = planner.root_node_ok(|rtt: &mut RandomTree<S::State>| {
+        Ok(RttNodeFocus { node_ref: rtt.root(), goal_reached: root_goal, })
+    });
+    index.insert(root_point, planner_node.node_ref().node_ref);
+    let mut tracker = StatsTracker::new(1);
     loop {
         if planner_node.node_ref().goal_reached {
-            let path = planner_node.into_path_ok(|rtt, focus: RttNodeFocus| focus.into_direct_path(rtt));;
-            tx.send(SlavePacket::RouteDone(path)).ok();
+            let (path, directions) = planner_node.into_path_ok(|rtt, focus: RttNodeFocus| {
+                let mut states: Vec<_> = rtt.into_path(focus.node_ref).collect();
+                states.reverse();
+                Ok(motion_path(space, &states))
+            });
+            send_directed_route(tx, trans, &mut rng, path, directions, &mut tracker);
             return false;
         }
 
         if debug {
             debug_image.routes_segs.clear();
-            let mut visited: HashSet<(NodeRef, NodeRef)> = HashSet::new();
-
-            let rtt = planner_node.rtt();
-            let states = rtt.states();
-            for (mut dst_node_ref, mut dst) in states.children {
-                for (src_node_ref, src) in rtt.path_iter(&dst_node_ref).skip(1) {
-                    let visited_key = (src_node_ref, dst_node_ref);
-                    if visited.contains(&visited_key) {
-                        break;
-                    } else {
-                        visited.insert(visited_key);
-                        debug_image.routes_segs.push((src.clone(), dst.clone()));
-                    }
-                    dst_node_ref = src_node_ref;
-                    dst = src;
-                }
-            }
+            collect_motions(space, planner_node.rtt(), &mut debug_image.routes_segs);
         }
 
         let mut planner_ready_to_sample = planner_node.prepare_sample_ok(|_rtt: &mut _, _focus| Ok(()));
         loop {
-            match rx.try_recv() {
-                Ok(MasterPacket::Solve(..)) =>
-                    (),
-                Ok(MasterPacket::SolveDebug(..)) =>
+            match poll_interrupt(rx, &mut last_ack, &tracker, &trans.params.budget) {
+                None =>
                     (),
-                Ok(MasterPacket::DebugTickAck(ack)) =>
-                    last_ack = ack,
-                Ok(MasterPacket::Terminate) =>
-                    return true,
-                Ok(MasterPacket::Abort) =>
+                Some(Interrupt::Abort) =>
                     return false,
-                Err(mpsc::TryRecvError::Empty) =>
-                    (),
-                Err(mpsc::TryRecvError::Disconnected) =>
+                Some(Interrupt::Terminate) =>
                     return true,
+                Some(Interrupt::OutOfBudget(failure)) => {
+                    send_failure(tx, failure, &mut tracker);
+                    return false;
+                },
             }
 
             let planner_sample = planner_ready_to_sample.sample_ok(|_rtt: &mut _| {
-                Ok(Point {
-                    x: rng.gen_range(trans.field.config.field_area.0, trans.field.config.field_area.2),
-                    y: rng.gen_range(trans.field.config.field_area.1, trans.field.config.field_area.3),
-                })
+                Ok(space.sample(&mut rng))
             });
 
-            let planner_closest = planner_sample.closest_to_sample_ok(|rtt: &mut RandomTree<Point>, sample: &Point| {
-                let mut closest;
-                {
-                    let points = rtt.states();
-                    closest = (points.root.0, sample.sq_dist(points.root.1));
-                    for (node_ref, point) in points.children {
-                        let sq_dist = sample.sq_dist(point);
-                        if sq_dist < closest.1 {
-                            closest = (node_ref, sq_dist);
-                        }
-                    }
-                }
-                Ok(closest.0)
+            let planner_closest = planner_sample.closest_to_sample_ok(|rtt: &mut RandomTree<S::State>, sample: &S::State| {
+                let distance = |node_ref: &NodeRef| space.distance(rtt.get_state(node_ref), sample);
+                Ok(index.nearest(&space.position(sample), distance, |_| true).unwrap())
             });
 
-            let has_route = trans.has_route(planner_closest.rtt(), planner_closest.node_ref(), planner_closest.sample());
+            let src = planner_closest.rtt().get_state(planner_closest.node_ref()).clone();
+            let dst = space.steer(&src, planner_closest.sample(), trans.params.step);
+            let has_route = match dst {
+                Some(ref dst) =>
+                    space.is_valid_motion(&src, dst),
+                None =>
+                    false,
+            };
+            tracker.sampled(has_route);
+            tracker.report(tx);
 
             if debug {
-                let rtt = planner_closest.rtt();
-                let closest_ref = planner_closest.node_ref();
-                let src = rtt.get_state(closest_ref).clone();
-                let dst = planner_closest.sample().clone();
+                let src_point = space.position(&src);
+                let dst_point = dst.as_ref().map(|dst| space.position(dst)).unwrap_or(src_point);
                 debug_image.sample_seg = if has_route {
-                    SampleTry::Passable(src, dst)
+                    SampleTry::Passable(src_point, dst_point)
                 } else {
-                    SampleTry::Blocked(src, dst)
+                    SampleTry::Blocked(src_point, dst_point)
                 };
-                if debug_image.tick_id == last_ack {
-                    debug_image.tick_id += 1;
-                    tx.send(SlavePacket::DebugTick(debug_image.clone())).ok();
+                send_debug_tick(tx, &mut debug_image, last_ack);
+            }
+
+            match dst {
+                Some(dst) if has_route => {
+                    planner_node = planner_closest.has_transition_ok(|rtt: &mut RandomTree<S::State>, node_ref: NodeRef, _sample| {
+                        let point = space.position(&dst);
+                        let goal_reached = space.is_goal(&dst);
+                        let node_ref = rtt.expand(node_ref, dst);
+                        index.insert(point, node_ref);
+                        tracker.stats.nodes += 1;
+                        Ok(RttNodeFocus { node_ref, goal_reached, })
+                    });
+                    break;
+                },
+                _ =>
+                    planner_ready_to_sample = planner_closest.no_transition_ok(|_rtt: &mut _, _node_ref| Ok(())),
+            }
+        }
+    }
+}
+
+// positions along the motions through the states with the direction of travel for every segment
+fn motion_path<S>(space: &S, states: &[S::State]) -> (Vec<Point>, Vec<Direction>) where S: StateSpace {
+    let mut path = vec![space.position(&states[0])];
+    let mut directions = Vec::new();
+    for pair in states.windows(2) {
+        for (state, direction) in space.motion(&pair[0], &pair[1]) {
+            path.push(space.position(&state));
+            directions.push(direction);
+        }
+    }
+    (path, directions)
+}
+
+fn collect_motions<S>(space: &S, rtt: &RandomTree<S::State>, segs: &mut Vec<(Point, Point)>) where S: StateSpace {
+    for (node_ref, state) in rtt.states().children {
+        if let Some((_, parent)) = rtt.path_iter(&node_ref).nth(1) {
+            let mut src = space.position(parent);
+            for (dst, _) in space.motion(parent, state) {
+                let dst = space.position(&dst);
+                segs.push((src, dst));
+                src = dst;
+            }
+        }
+    }
+}
+
+struct LazyNode<T> {
+    state: T,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    // the edge from the parent has already been checked for collisions
+    checked: bool,
+    pruned: bool,
+}
+
+// tree grown without collision checks: the edges are validated only on the way to the finish, and the
+// subtrees hanging from the blocked ones are pruned
+struct LazyTree<T> {
+    nodes: Vec<LazyNode<T>>,
+    // pruned nodes stay in `nodes` and `index` as tombstones until there are too many of them
+    index: NearestIndex<usize>,
+    pruned: usize,
+}
+
+impl<T> LazyTree<T> where T: Clone {
+    fn add(&mut self, parent: Option<usize>, state: T, point: Point) -> usize {
+        let node = self.nodes.len();
+        self.nodes.push(LazyNode { state, parent, children: Vec::new(), checked: parent.is_none(), pruned: false, });
+        if let Some(parent) = parent {
+            self.nodes[parent].children.push(node);
+        }
+        self.index.insert(point, node);
+        node
+    }
+
+    // nodes from the root to the given one
+    fn branch(&self, mut node: usize) -> Vec<usize> {
+        let mut branch = vec![node];
+        while let Some(parent) = self.nodes[node].parent {
+            branch.push(parent);
+            node = parent;
+        }
+        branch.reverse();
+        branch
+    }
+
+    fn nearest<D>(&self, point: &Point, distance: D) -> Option<usize> where D: Fn(&T) -> f64 {
+        self.index.nearest(point, |&node| distance(&self.nodes[node].state), |&node| !self.nodes[node].pruned)
+    }
+
+    // drops the node with all its descendants, returns how many nodes are gone
+    fn prune<F>(&mut self, node: usize, position: F) -> usize where F: Fn(&T) -> Point {
+        if let Some(parent) = self.nodes[node].parent {
+            self.nodes[parent].children.retain(|&child| child != node);
+        }
+        let mut pruned = 0;
+        let mut pending = vec![node];
+        while let Some(index) = pending.pop() {
+            self.nodes[index].pruned = true;
+            pending.extend(self.nodes[index].children.drain(..));
+            pruned += 1;
+        }
+        self.pruned += pruned;
+        // compacting only once tombstones make up half of the tree keeps pruning cost linear in the pruned nodes
+        if self.pruned * 2 > self.nodes.len() {
+            self.compact(position);
+        }
+        pruned
+    }
+
+    // renumbers the surviving nodes, the spatial index does not support removal so it is rebuilt
+    fn compact<F>(&mut self, position: F) where F: Fn(&T) -> Point {
+        let mut renumbered = Vec::with_capacity(self.nodes.len());
+        let mut alive = 0;
+        for node in self.nodes.iter() {
+            renumbered.push(if node.pruned { None } else { alive += 1; Some(alive - 1) });
+        }
+        let nodes = mem::replace(&mut self.nodes, Vec::with_capacity(alive));
+        self.index.clear();
+        for mut node in nodes.into_iter().filter(|node| !node.pruned) {
+            node.parent = node.parent.and_then(|parent| renumbered[parent]);
+            node.children = node.children.iter().filter_map(|&child| renumbered[child]).collect();
+            self.index.insert(position(&node.state), self.nodes.len());
+            self.nodes.push(node);
+        }
+        self.pruned = 0;
+    }
+}
+
+fn run_solve_lazy<S>(rx: &mpsc::Receiver<MasterPacket>, tx: &mpsc::Sender<SlavePacket>, trans: &Trans, space: &S, debug: bool) -> bool where S: StateSpace {
+    let mut rng = seeded_rng(trans.params.seed);
+    let mut debug_image = DebugImage {
+        tick_id: 0,
+        routes_segs: Vec::new(),
+        sample_seg: SampleTry::None,
+        workers_segs: Vec::new(),
+    };
+    let mut last_ack = 0;
+
+    let mut tree = LazyTree {
+        nodes: Vec::new(),
+        index: NearestIndex::new(space, trans),
+        pruned: 0,
+    };
+    let root = space.start();
+    let root_point = space.position(&root);
+    let mut goal = if space.is_goal(&root) { Some(0) } else { None };
+    tree.add(None, root, root_point);
+    let mut tracker = StatsTracker::new(1);
+    loop {
+        if let Some(node) = goal.take() {
+            let branch = tree.branch(node);
+            let mut blocked = None;
+            for pair in branch.windows(2) {
+                if tree.nodes[pair[1]].checked {
+                    continue;
+                }
+                let has_route = space.is_valid_motion(&tree.nodes[pair[0]].state, &tree.nodes[pair[1]].state);
+                tracker.checked(has_route);
+                if has_route {
+                    tree.nodes[pair[1]].checked = true;
+                } else {
+                    blocked = Some(pair[1]);
+                    break;
+                }
+            }
+            match blocked {
+                None => {
+                    let states: Vec<_> = branch.iter().map(|&node| tree.nodes[node].state.clone()).collect();
+                    let (path, directions) = motion_path(space, &states);
+                    send_directed_route(tx, trans, &mut rng, path, directions, &mut tracker);
+                    return false;
+                },
+                Some(node) =>
+                    tracker.stats.nodes -= tree.prune(node, |state| space.position(state)),
+            }
+        }
+
+        match poll_interrupt(rx, &mut last_ack, &tracker, &trans.params.budget) {
+            None =>
+                (),
+            Some(Interrupt::Abort) =>
+                return false,
+            Some(Interrupt::Terminate) =>
+                return true,
+            Some(Interrupt::OutOfBudget(failure)) => {
+                send_failure(tx, failure, &mut tracker);
+                return false;
+            },
+        }
+
+        let sample = space.sample(&mut rng);
+        let closest = tree.nearest(&space.position(&sample), |state| space.distance(state, &sample)).unwrap();
+        let src = tree.nodes[closest].state.clone();
+        let dst = space.steer(&src, &sample, trans.params.step);
+        tracker.stats.samples += 1;
+        tracker.report(tx);
+
+        if debug {
+            debug_image.routes_segs.clear();
+            for node in tree.nodes.iter().filter(|node| !node.pruned) {
+                if let Some(parent) = node.parent {
+                    debug_image.routes_segs.push((space.position(&tree.nodes[parent].state), space.position(&node.state)));
+                }
+            }
+            let src_point = space.position(&src);
+            let dst_point = dst.as_ref().map(|dst| space.position(dst)).unwrap_or(src_point);
+            debug_image.sample_seg = SampleTry::Passable(src_point, dst_point);
+            send_debug_tick(tx, &mut debug_image, last_ack);
+        }
+
+        if let Some(dst) = dst {
+            let point = space.position(&dst);
+            let reached = space.is_goal(&dst);
+            let node = tree.add(Some(closest), dst, point);
+            tracker.stats.nodes += 1;
+            if reached {
+                goal = Some(node);
+            }
+        }
+    }
+}
+
+// how long the workers coordinator sleeps when none of the workers has sent anything
+const WORKERS_POLL_MS: u64 = 1;
+
+struct Worker {
+    tx: mpsc::Sender<MasterPacket>,
+    rx: mpsc::Receiver<SlavePacket>,
+    thread: thread::JoinHandle<bool>,
+    stats: Option<SolveStats>,
+    done: bool,
+}
+
+// plain RTT trees grown in parallel threads from consecutive seeds: the first route found wins, unless there is
+// a budget, in which case every worker spends it (or finds its route) and the shortest route is kept
+fn run_solve_workers(rx: &mpsc::Receiver<MasterPacket>, tx: &mpsc::Sender<SlavePacket>, trans: Trans, debug: bool) -> bool {
+    let mut workers = Vec::with_capacity(trans.params.workers);
+    for index in 0 .. trans.params.workers {
+        let (master_tx, worker_rx) = mpsc::channel();
+        let (worker_tx, master_rx) = mpsc::channel();
+        let field = trans.field.clone();
+        let params = SolveParams { seed: trans.params.seed.wrapping_add(index as u64), workers: 1, ..trans.params.clone() };
+        let spawned = thread::Builder::new()
+            .name(format!("RTT demo worker #{}", index))
+            .spawn(move || run_rtt(&worker_rx, &worker_tx, &Trans::new(field, params), debug));
+        if let Ok(thread) = spawned {
+            workers.push(Worker { tx: master_tx, rx: master_rx, thread, stats: None, done: false, });
+        }
+    }
+    if workers.is_empty() {
+        return run_rtt(rx, tx, &trans, debug);
+    }
+
+    let budget = trans.params.budget;
+    let keep_best = budget.max_iterations.is_some() || budget.max_nodes.is_some() || budget.deadline.is_some();
+    let mut debug_image = DebugImage {
+        tick_id: 0,
+        routes_segs: Vec::new(),
+        sample_seg: SampleTry::None,
+        workers_segs: vec![Vec::new(); workers.len()],
+    };
+    let mut last_ack = 0;
+    let mut tracker = StatsTracker::new(workers.len());
+    let mut best: Option<Route> = None;
+    let mut failure = None;
+    loop {
+        match poll_master(rx, &mut last_ack) {
+            None =>
+                (),
+            Some(Interrupt::Terminate) => {
+                stop_workers(workers);
+                return true;
+            },
+            Some(Interrupt::Abort) | Some(Interrupt::OutOfBudget(..)) => {
+                stop_workers(workers);
+                return false;
+            },
+        }
+
+        let mut received = false;
+        let mut routes = Vec::new();
+        for (index, worker) in workers.iter_mut().enumerate() {
+            while !worker.done {
+                match worker.rx.try_recv() {
+                    Ok(SlavePacket::RouteDone(route)) => {
+                        worker.done = true;
+                        routes.push(route);
+                    },
+                    Ok(SlavePacket::RouteFailed(worker_failure)) => {
+                        worker.done = true;
+                        failure = Some(worker_failure);
+                    },
+                    // plain RTT neither improves routes nor checks the field feasibility itself
+                    Ok(SlavePacket::RouteImproved(..)) | Ok(SlavePacket::RouteInfeasible(..)) =>
+                        (),
+                    Ok(SlavePacket::DebugTick(worker_image)) => {
+                        worker.tx.send(MasterPacket::DebugTickAck(worker_image.tick_id)).ok();
+                        debug_image.workers_segs[index] = worker_image.routes_segs;
+                        debug_image.sample_seg = worker_image.sample_seg;
+                    },
+                    Ok(SlavePacket::Stats(stats)) =>
+                        worker.stats = Some(stats),
+                    Err(mpsc::TryRecvError::Empty) =>
+                        break,
+                    Err(mpsc::TryRecvError::Disconnected) =>
+                        worker.done = true,
                 }
-                ::std::thread::sleep(::std::time::Duration::from_millis(100));
+                received = true;
+            }
+        }
+
+        tracker.stats.samples = workers.iter().map(|worker| worker.stats.map_or(0, |stats| stats.samples)).sum();
+        tracker.stats.blocked = workers.iter().map(|worker| worker.stats.map_or(0, |stats| stats.blocked)).sum();
+        tracker.stats.passable = workers.iter().map(|worker| worker.stats.map_or(0, |stats| stats.passable)).sum();
+        tracker.stats.nodes = workers.iter().map(|worker| worker.stats.map_or(1, |stats| stats.nodes)).sum();
+        tracker.report(tx);
+
+        for route in routes {
+            let shorter = match best {
+                Some(ref best) =>
+                    path_length(&route.path) < path_length(&best.path),
+                None =>
+                    true,
+            };
+            if shorter {
+                best = Some(route);
             }
+        }
+        if best.is_some() && (!keep_best || workers.iter().all(|worker| worker.done)) {
+            stop_workers(workers);
+            let mut route = best.unwrap();
+            route.stats = tracker.finish(tx, path_length(&route.path));
+            tx.send(SlavePacket::RouteDone(route)).ok();
+            return false;
+        }
+        if workers.iter().all(|worker| worker.done) {
+            stop_workers(workers);
+            return match failure {
+                Some(failure) => {
+                    send_failure(tx, failure, &mut tracker);
+                    false
+                },
+                // every worker has disconnected without a word, so the slave is no better off
+                None =>
+                    true,
+            };
+        }
+
+        if debug && received {
+            send_debug_tick(tx, &mut debug_image, last_ack);
+        } else if !received {
+            thread::sleep(Duration::from_millis(WORKERS_POLL_MS));
+        }
+    }
+}
 
-            if has_route {
-                planner_node = planner_closest.has_transition_ok(|rtt: &mut RandomTree<Point>, node_ref: NodeRef, sample| {
-                    let node_ref = rtt.expand(node_ref, sample);
-                    let goal_reached = trans.goal_reached(rtt.get_state(&node_ref));
-                    Ok(RttNodeFocus { node_ref, goal_reached, })
-                });
+fn stop_workers(workers: Vec<Worker>) {
+    for worker in workers.iter() {
+        worker.tx.send(MasterPacket::Abort).ok();
+    }
+    for worker in workers {
+        worker.thread.join().ok();
+    }
+}
+
+fn collect_segments(rtt: &RandomTree<Point>, segs: &mut Vec<(Point, Point)>) {
+    let mut visited: HashSet<(NodeRef, NodeRef)> = HashSet::new();
+    let states = rtt.states();
+    for (mut dst_node_ref, mut dst) in states.children {
+        for (src_node_ref, src) in rtt.path_iter(&dst_node_ref).skip(1) {
+            let visited_key = (src_node_ref, dst_node_ref);
+            if visited.contains(&visited_key) {
                 break;
             } else {
-                planner_ready_to_sample = planner_closest.no_transition_ok(|_rtt: &mut _, _node_ref| Ok(()));
+                visited.insert(visited_key);
+                segs.push((src.clone(), dst.clone()));
+            }
+            dst_node_ref = src_node_ref;
+            dst = src;
+        }
+    }
+}
+
+enum Interrupt {
+    Abort,
+    Terminate,
+    OutOfBudget(SolveFailure),
+}
+
+// master requests take precedence over the budget
+fn poll_interrupt(rx: &mpsc::Receiver<MasterPacket>, last_ack: &mut usize, tracker: &StatsTracker, budget: &Budget) -> Option<Interrupt> {
+    poll_master(rx, last_ack)
+        .or_else(|| tracker.over_budget(budget).map(Interrupt::OutOfBudget))
+}
+
+fn poll_master(rx: &mpsc::Receiver<MasterPacket>, last_ack: &mut usize) -> Option<Interrupt> {
+    match rx.try_recv() {
+        Ok(MasterPacket::Solve(..)) =>
+            None,
+        Ok(MasterPacket::SolveDebug(..)) =>
+            None,
+        Ok(MasterPacket::DebugTickAck(ack)) => {
+            *last_ack = ack;
+            None
+        },
+        Ok(MasterPacket::Terminate) =>
+            Some(Interrupt::Terminate),
+        Ok(MasterPacket::Abort) =>
+            Some(Interrupt::Abort),
+        Err(mpsc::TryRecvError::Empty) =>
+            None,
+        Err(mpsc::TryRecvError::Disconnected) =>
+            Some(Interrupt::Terminate),
+    }
+}
+
+fn send_failure(tx: &mpsc::Sender<SlavePacket>, failure: SolveFailure, tracker: &mut StatsTracker) {
+    tracker.update_elapsed();
+    tx.send(SlavePacket::Stats(tracker.stats)).ok();
+    tx.send(SlavePacket::RouteFailed(failure)).ok();
+}
+
+fn send_route<R>(tx: &mpsc::Sender<SlavePacket>, trans: &Trans, rng: &mut R, path: Vec<Point>, tracker: &mut StatsTracker) where R: Rng {
+    let directions = path.iter().skip(1).map(|_| Direction::Forward).collect();
+    send_directed_route(tx, trans, rng, path, directions, tracker);
+}
+
+fn send_directed_route<R>(
+    tx: &mpsc::Sender<SlavePacket>,
+    trans: &Trans,
+    rng: &mut R,
+    path: Vec<Point>,
+    directions: Vec<Direction>,
+    tracker: &mut StatsTracker,
+)
+    where R: Rng
+{
+    let route = make_route(tx, trans, rng, path, directions, tracker);
+    tx.send(SlavePacket::RouteDone(route)).ok();
+}
+
+fn send_improved_route<R>(tx: &mpsc::Sender<SlavePacket>, trans: &Trans, rng: &mut R, path: Vec<Point>, tracker: &mut StatsTracker) where R: Rng {
+    let directions = path.iter().skip(1).map(|_| Direction::Forward).collect();
+    let route = make_route(tx, trans, rng, path, directions, tracker);
+    tx.send(SlavePacket::RouteImproved(route)).ok();
+}
+
+fn make_route<R>(
+    tx: &mpsc::Sender<SlavePacket>,
+    trans: &Trans,
+    rng: &mut R,
+    path: Vec<Point>,
+    directions: Vec<Direction>,
+    tracker: &mut StatsTracker,
+)
+    -> Route
+    where R: Rng
+{
+    let smoothed = match (trans.params.kinematics, trans.params.smoothing) {
+        // car routes are sent as is: straight shortcuts would break the kinematic constraints
+        (Kinematics::Dubins, _) | (Kinematics::ReedsShepp, _) | (Kinematics::Holonomic, Smoothing::Off) =>
+            None,
+        (Kinematics::Holonomic, Smoothing::Shortcut) =>
+            Some(shortcut_path(trans, rng, path.clone())),
+        (Kinematics::Holonomic, Smoothing::Spline) => {
+            let shortcut = shortcut_path(trans, rng, path.clone());
+            Some(spline_path(trans, shortcut))
+        },
+    };
+    let stats = tracker.finish(tx, path_length(&path));
+    Route { path, directions, smoothed, robot_radius: trans.params.robot_radius, stats, }
+}
+
+const STATS_INTERVAL_MS: u64 = 250;
+
+struct StatsTracker {
+    stats: SolveStats,
+    started: Instant,
+    reported: Instant,
+}
+
+impl StatsTracker {
+    fn new(nodes: usize) -> StatsTracker {
+        let now = Instant::now();
+        StatsTracker {
+            stats: SolveStats {
+                samples: 0,
+                blocked: 0,
+                passable: 0,
+                nodes,
+                elapsed: 0.,
+                route_length: None,
+            },
+            started: now,
+            reported: now,
+        }
+    }
+
+    fn sampled(&mut self, has_route: bool) {
+        self.stats.samples += 1;
+        self.checked(has_route);
+    }
+
+    fn checked(&mut self, has_route: bool) {
+        if has_route {
+            self.stats.passable += 1;
+        } else {
+            self.stats.blocked += 1;
+        }
+    }
+
+    fn update_elapsed(&mut self) {
+        let elapsed = self.started.elapsed();
+        self.stats.elapsed = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000.;
+    }
+
+    fn over_budget(&self, budget: &Budget) -> Option<SolveFailure> {
+        match (budget.max_iterations, budget.max_nodes, budget.deadline) {
+            (Some(max_iterations), _, _) if self.stats.samples >= max_iterations =>
+                Some(SolveFailure::IterationsExhausted),
+            (_, Some(max_nodes), _) if self.stats.nodes >= max_nodes =>
+                Some(SolveFailure::NodesExhausted),
+            (_, _, Some(deadline)) if self.started.elapsed() >= Duration::from_millis((deadline * 1000.) as u64) =>
+                Some(SolveFailure::DeadlineExpired),
+            _ =>
+                None,
+        }
+    }
+
+    fn report(&mut self, tx: &mpsc::Sender<SlavePacket>) {
+        if self.reported.elapsed() >= Duration::from_millis(STATS_INTERVAL_MS) {
+            self.reported = Instant::now();
+            self.update_elapsed();
+            tx.send(SlavePacket::Stats(self.stats)).ok();
+        }
+    }
+
+    fn finish(&mut self, tx: &mpsc::Sender<SlavePacket>, route_length: f64) -> SolveStats {
+        self.update_elapsed();
+        self.stats.route_length = Some(route_length);
+        tx.send(SlavePacket::Stats(self.stats)).ok();
+        self.stats
+    }
+}
+
+const SHORTCUT_ATTEMPTS: usize = 200;
+const SPLINE_ITERATIONS: usize = 4;
+
+fn shortcut_path<R>(trans: &Trans, rng: &mut R, mut path: Vec<Point>) -> Vec<Point> where R: Rng {
+    for _ in 0 .. SHORTCUT_ATTEMPTS {
+        if path.len() < 3 {
+            break;
+        }
+        let a = rng.gen_range(0, path.len() - 2);
+        let b = rng.gen_range(a + 2, path.len());
+        if trans.has_route(&path[a], &path[b]) {
+            path.drain(a + 1 .. b);
+        }
+    }
+    path
+}
+
+// Chaikin corner cutting (converges to a quadratic B-spline), stops at the first iteration which hits an obstacle
+fn spline_path(trans: &Trans, mut path: Vec<Point>) -> Vec<Point> {
+    for _ in 0 .. SPLINE_ITERATIONS {
+        if path.len() < 3 {
+            break;
+        }
+        let last = path.len() - 2;
+        let mut next_path = Vec::with_capacity(path.len() * 2);
+        next_path.push(path[0]);
+        for (index, seg) in path.windows(2).enumerate() {
+            let (a, b) = (&seg[0], &seg[1]);
+            if index > 0 {
+                next_path.push(Point { x: 0.75 * a.x + 0.25 * b.x, y: 0.75 * a.y + 0.25 * b.y, });
+            }
+            if index < last {
+                next_path.push(Point { x: 0.25 * a.x + 0.75 * b.x, y: 0.25 * a.y + 0.75 * b.y, });
+            }
+        }
+        next_path.push(path[path.len() - 1]);
+        if next_path.windows(2).all(|seg| trans.has_route(&seg[0], &seg[1])) {
+            path = next_path;
+        } else {
+            break;
+        }
+    }
+    path
+}
+
+fn send_debug_tick(tx: &mpsc::Sender<SlavePacket>, debug_image: &mut DebugImage, last_ack: usize) {
+    if debug_image.tick_id == last_ack {
+        debug_image.tick_id += 1;
+        tx.send(SlavePacket::DebugTick(debug_image.clone())).ok();
+    }
+    thread::sleep(Duration::from_millis(100));
+}
+
+// how many more samples RRT* spends improving the route after the goal has been reached for the first time
+const STAR_REFINE_SAMPLES: usize = 2000;
+// anytime RRT* reports a new route only when it is at least this much shorter than the last reported one
+const STAR_MIN_IMPROVEMENT: f64 = 0.5;
+
+struct StarNode {
+    point: Point,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    cost: f64,
+}
+
+struct StarTree {
+    nodes: Vec<StarNode>,
+    index: NodesIndex<usize>,
+}
+
+impl StarTree {
+    fn new(root: Point, mut index: NodesIndex<usize>) -> StarTree {
+        index.insert(root, 0);
+        StarTree {
+            nodes: vec![StarNode { point: root, parent: None, children: Vec::new(), cost: 0., }],
+            index,
+        }
+    }
+
+    fn nearest(&self, point: &Point) -> usize {
+        self.index.nearest(point).unwrap()
+    }
+
+    fn near(&self, point: &Point, radius: f64) -> Vec<usize> {
+        self.index.within(point, radius)
+    }
+
+    fn cost_via(&self, parent: usize, point: &Point) -> f64 {
+        let node = &self.nodes[parent];
+        node.cost + node.point.sq_dist(point).sqrt()
+    }
+
+    fn add(&mut self, parent: usize, point: Point) -> usize {
+        let cost = self.cost_via(parent, &point);
+        let index = self.nodes.len();
+        self.nodes.push(StarNode { point, parent: Some(parent), children: Vec::new(), cost, });
+        self.nodes[parent].children.push(index);
+        self.index.insert(point, index);
+        index
+    }
+
+    fn reparent(&mut self, node: usize, parent: usize) {
+        if let Some(prev_parent) = self.nodes[node].parent {
+            self.nodes[prev_parent].children.retain(|&child| child != node);
+        }
+        let point = self.nodes[node].point;
+        let delta = self.cost_via(parent, &point) - self.nodes[node].cost;
+        self.nodes[node].parent = Some(parent);
+        self.nodes[parent].children.push(node);
+
+        let mut pending = vec![node];
+        while let Some(index) = pending.pop() {
+            self.nodes[index].cost += delta;
+            pending.extend(self.nodes[index].children.iter().cloned());
+        }
+    }
+
+    fn path(&self, mut node: usize) -> Vec<Point> {
+        let mut rev_path = vec![self.nodes[node].point];
+        while let Some(parent) = self.nodes[node].parent {
+            rev_path.push(self.nodes[parent].point);
+            node = parent;
+        }
+        rev_path.reverse();
+        rev_path
+    }
+
+    fn segments(&self, segs: &mut Vec<(Point, Point)>) {
+        segs.clear();
+        for node in self.nodes.iter() {
+            if let Some(parent) = node.parent {
+                segs.push((self.nodes[parent].point, node.point));
+            }
+        }
+    }
+}
+
+fn star_radius(trans: &Trans, nodes_count: usize) -> f64 {
+    let (min_x, min_y, max_x, max_y) = trans.field.config.field_area;
+    let area = (max_x - min_x) * (max_y - min_y);
+    let gamma = 2. * (1.5 * area / ::std::f64::consts::PI).sqrt();
+    let n = (nodes_count + 1) as f64;
+    let radius = gamma * (n.ln() / n).sqrt();
+    match trans.params.step {
+        Some(step) if step < radius =>
+            step,
+        _ =>
+            radius,
+    }
+}
+
+fn run_solve_star(rx: &mpsc::Receiver<MasterPacket>, tx: &mpsc::Sender<SlavePacket>, trans: Trans, debug: bool) -> bool {
+    let mut rng = seeded_rng(trans.params.seed);
+    let mut debug_image = DebugImage {
+        tick_id: 0,
+        routes_segs: Vec::new(),
+        sample_seg: SampleTry::None,
+        workers_segs: Vec::new(),
+    };
+    let mut last_ack = 0;
+
+    let index = NodesIndex::new(trans.field.config.field_area, trans.params.spatial_index);
+    let mut tree = StarTree::new(trans.field.start, index);
+    if trans.goal_reached(&trans.field.start) {
+        send_route(tx, &trans, &mut rng, tree.path(0), &mut StatsTracker::new(1));
+        return false;
+    }
+
+    let mut goal_nodes = Vec::new();
+    let mut refine_samples = 0;
+    let mut reported_cost = ::std::f64::INFINITY;
+    let mut tracker = StatsTracker::new(1);
+    loop {
+        // rewiring keeps lowering the costs, so the best goal node is looked up anew every time
+        let best = goal_nodes.iter()
+            .cloned()
+            .min_by(|&a: &usize, &b: &usize| tree.nodes[a].cost.partial_cmp(&tree.nodes[b].cost).unwrap());
+        if let Some(best) = best {
+            if trans.params.anytime {
+                if tree.nodes[best].cost < reported_cost - STAR_MIN_IMPROVEMENT {
+                    reported_cost = tree.nodes[best].cost;
+                    send_improved_route(tx, &trans, &mut rng, tree.path(best), &mut tracker);
+                }
+            } else if refine_samples >= STAR_REFINE_SAMPLES {
+                send_route(tx, &trans, &mut rng, tree.path(best), &mut tracker);
+                return false;
+            } else {
+                refine_samples += 1;
+            }
+        }
+
+        match poll_interrupt(rx, &mut last_ack, &tracker, &trans.params.budget) {
+            None =>
+                (),
+            Some(Interrupt::Abort) =>
+                return false,
+            Some(Interrupt::Terminate) =>
+                return true,
+            // the route found so far is good enough when there is no budget left to improve it
+            Some(Interrupt::OutOfBudget(..)) if best.is_some() => {
+                send_route(tx, &trans, &mut rng, tree.path(best.unwrap()), &mut tracker);
+                return false;
+            },
+            Some(Interrupt::OutOfBudget(failure)) => {
+                send_failure(tx, failure, &mut tracker);
+                return false;
+            },
+        }
+
+        // once there is a route only the samples which could make it shorter are worth trying (informed RRT*)
+        let sample = match best {
+            Some(best) =>
+                trans.sample_informed(&mut rng, tree.nodes[best].cost),
+            None =>
+                trans.sample(&mut rng),
+        };
+        let nearest = tree.nearest(&sample);
+        let point = trans.steer(&tree.nodes[nearest].point, &sample);
+        let has_route = trans.has_route(&tree.nodes[nearest].point, &point);
+        tracker.sampled(has_route);
+        tracker.report(tx);
+
+        if debug {
+            tree.segments(&mut debug_image.routes_segs);
+            let src = tree.nodes[nearest].point;
+            debug_image.sample_seg = if has_route {
+                SampleTry::Passable(src, point)
+            } else {
+                SampleTry::Blocked(src, point)
+            };
+            send_debug_tick(tx, &mut debug_image, last_ack);
+        }
+
+        if !has_route {
+            continue;
+        }
+
+        // choose the cheapest parent in the neighbourhood
+        let radius = star_radius(&trans, tree.nodes.len());
+        let near = tree.near(&point, radius);
+        let mut parent = (nearest, tree.cost_via(nearest, &point));
+        for &candidate in near.iter() {
+            let cost = tree.cost_via(candidate, &point);
+            if cost < parent.1 && trans.has_route(&tree.nodes[candidate].point, &point) {
+                parent = (candidate, cost);
+            }
+        }
+        let node = tree.add(parent.0, point);
+        tracker.stats.nodes = tree.nodes.len();
+
+        // rewire the neighbourhood through the new node
+        for &candidate in near.iter() {
+            if candidate == parent.0 {
+                continue;
+            }
+            let cost = tree.cost_via(node, &tree.nodes[candidate].point);
+            if cost < tree.nodes[candidate].cost && trans.has_route(&point, &tree.nodes[candidate].point) {
+                tree.reparent(candidate, node);
+            }
+        }
+
+        if trans.goal_reached(&point) {
+            goal_nodes.push(node);
+        }
+    }
+}
+
+fn run_solve_connect(rx: &mpsc::Receiver<MasterPacket>, tx: &mpsc::Sender<SlavePacket>, trans: Trans, debug: bool) -> bool {
+    let mut rng = seeded_rng(trans.params.seed);
+    let mut debug_image = DebugImage {
+        tick_id: 0,
+        routes_segs: Vec::new(),
+        sample_seg: SampleTry::None,
+        workers_segs: Vec::new(),
+    };
+    let mut last_ack = 0;
+
+    // tree "a" is the one being extended towards the sample, tree "b" then tries to connect to the new node
+    let mut tree_a = EmptyRandomTree::new().add_root(trans.field.start);
+    let mut tree_b = EmptyRandomTree::new().add_root(trans.field.config.finish_area.center);
+    let mut index_a = NodesIndex::new(trans.field.config.field_area, trans.params.spatial_index);
+    let mut index_b = NodesIndex::new(trans.field.config.field_area, trans.params.spatial_index);
+    index_a.insert(trans.field.start, tree_a.root());
+    index_b.insert(trans.field.config.finish_area.center, tree_b.root());
+    let mut a_is_start = true;
+    let mut tracker = StatsTracker::new(2);
+    loop {
+        match poll_interrupt(rx, &mut last_ack, &tracker, &trans.params.budget) {
+            None =>
+                (),
+            Some(Interrupt::Abort) =>
+                return false,
+            Some(Interrupt::Terminate) =>
+                return true,
+            Some(Interrupt::OutOfBudget(failure)) => {
+                send_failure(tx, failure, &mut tracker);
+                return false;
+            },
+        }
+
+        let sample = trans.sample(&mut rng);
+        let closest_a = index_a.nearest(&sample).unwrap();
+        let point = trans.steer(tree_a.get_state(&closest_a), &sample);
+        let has_route = trans.has_route(tree_a.get_state(&closest_a), &point);
+        tracker.sampled(has_route);
+        tracker.report(tx);
+
+        if debug {
+            debug_image.routes_segs.clear();
+            collect_segments(&tree_a, &mut debug_image.routes_segs);
+            collect_segments(&tree_b, &mut debug_image.routes_segs);
+            let src = tree_a.get_state(&closest_a).clone();
+            debug_image.sample_seg = if has_route {
+                SampleTry::Passable(src, point)
+            } else {
+                SampleTry::Blocked(src, point)
+            };
+            send_debug_tick(tx, &mut debug_image, last_ack);
+        }
+
+        if has_route {
+            let node_a = tree_a.expand(closest_a, point);
+            index_a.insert(point, node_a);
+            tracker.stats.nodes += 1;
+            // greedily grow tree "b" towards the new node until it is either reached or blocked
+            let mut closest_b = index_b.nearest(&point).unwrap();
+            let connected = loop {
+                let src = tree_b.get_state(&closest_b).clone();
+                let dst = trans.steer(&src, &point);
+                if !trans.has_route(&src, &dst) {
+                    break false;
+                }
+                if dst.sq_dist(&point) <= 0. {
+                    break true;
+                }
+                closest_b = tree_b.expand(closest_b, dst);
+                index_b.insert(dst, closest_b);
+                tracker.stats.nodes += 1;
+            };
+            if connected {
+                let (start_tree, start_node, finish_tree, finish_node) = if a_is_start {
+                    (tree_a, node_a, tree_b, closest_b)
+                } else {
+                    (tree_b, closest_b, tree_a, node_a)
+                };
+                let mut path: Vec<_> = start_tree.into_path(start_node).collect();
+                path.reverse();
+                path.extend(finish_tree.into_path(finish_node));
+                send_route(tx, &trans, &mut rng, path, &mut tracker);
+                return false;
+            }
+        }
+
+        mem::swap(&mut tree_a, &mut tree_b);
+        mem::swap(&mut index_a, &mut index_b);
+        a_is_start = !a_is_start;
+    }
+}
+
+// how many nodes are sampled into the roadmap before the next query attempt
+const PRM_BATCH_NODES: usize = 200;
+// how many closest roadmap nodes a new one tries to connect to
+const PRM_NEIGHBOURS: usize = 10;
+
+struct Roadmap {
+    // what the roadmap has been built for: it is only reused while all of these stay the same
+    field_area: (f64, f64, f64, f64),
+    obstacles: Vec<Obstacle>,
+    robot_radius: f64,
+    boundary: Boundary,
+    step: Option<f64>,
+
+    nodes: Vec<Point>,
+    edges: Vec<Vec<(usize, f64)>>,
+    index: NodesIndex<usize>,
+    rng: ChaChaRng,
+}
+
+impl Roadmap {
+    fn new(trans: &Trans) -> Roadmap {
+        Roadmap {
+            field_area: trans.field.config.field_area,
+            obstacles: trans.field.obstacles.clone(),
+            robot_radius: trans.params.robot_radius,
+            boundary: trans.params.boundary,
+            step: trans.params.step,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            index: NodesIndex::new(trans.field.config.field_area, trans.params.spatial_index),
+            rng: seeded_rng(trans.params.seed),
+        }
+    }
+
+    fn fits(&self, trans: &Trans) -> bool {
+        self.field_area == trans.field.config.field_area &&
+            self.obstacles == trans.field.obstacles &&
+            self.robot_radius == trans.params.robot_radius &&
+            self.boundary == trans.params.boundary &&
+            self.step == trans.params.step
+    }
+
+    // samples a new node, returns false when it falls on an obstacle
+    fn grow(&mut self, trans: &Trans) -> bool {
+        let (min_x, min_y, max_x, max_y) = trans.free_area();
+        let point = Point {
+            x: self.rng.gen_range(min_x, max_x),
+            y: self.rng.gen_range(min_y, max_y),
+        };
+        if !trans.is_free(&point) {
+            return false;
+        }
+        let node = self.nodes.len();
+        let links = self.links(trans, &point);
+        self.nodes.push(point);
+        self.edges.push(Vec::new());
+        for (other, cost) in links {
+            self.edges[node].push((other, cost));
+            self.edges[other].push((node, cost));
+        }
+        self.index.insert(point, node);
+        true
+    }
+
+    // closest roadmap nodes reachable from the point along with the edge costs
+    fn links(&self, trans: &Trans, point: &Point) -> Vec<(usize, f64)> {
+        self.index.k_nearest(point, PRM_NEIGHBOURS)
+            .into_iter()
+            .filter_map(|other| {
+                let cost = point.sq_dist(&self.nodes[other]).sqrt();
+                let in_reach = match self.step {
+                    Some(step) =>
+                        cost <= step,
+                    None =>
+                        true,
+                };
+                if in_reach && trans.has_route(point, &self.nodes[other]) {
+                    Some((other, cost))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    // A* from the start point to the finish area, the start and the finish center are linked to the roadmap
+    // just for this query so that they can be moved around without rebuilding it
+    fn query(&self, trans: &Trans) -> Option<Vec<Point>> {
+        let start = trans.field.start;
+        let finish = trans.field.config.finish_area;
+        let start_links = self.links(trans, &start);
+        let finish_links = if trans.is_free(&finish.center) {
+            self.links(trans, &finish.center)
+        } else {
+            Vec::new()
+        };
+        let (start_id, finish_id) = (self.nodes.len(), self.nodes.len() + 1);
+        let point_of = |node: usize| if node == start_id {
+            start
+        } else if node == finish_id {
+            finish.center
+        } else {
+            self.nodes[node]
+        };
+        let heuristic = |point: &Point| (point.sq_dist(&finish.center).sqrt() - finish.radius).max(0.);
+
+        let mut costs = vec![::std::f64::INFINITY; self.nodes.len() + 2];
+        let mut parents = vec![None; self.nodes.len() + 2];
+        let mut closed = vec![false; self.nodes.len() + 2];
+        let mut queue = BinaryHeap::new();
+        costs[start_id] = 0.;
+        queue.push(Queued { priority: heuristic(&start), node: start_id, });
+        while let Some(Queued { node, .. }) = queue.pop() {
+            if closed[node] {
+                continue;
+            }
+            closed[node] = true;
+            let point = point_of(node);
+            if trans.goal_reached(&point) {
+                let mut rev_path = vec![point];
+                let mut current = node;
+                while let Some(parent) = parents[current] {
+                    rev_path.push(point_of(parent));
+                    current = parent;
+                }
+                rev_path.reverse();
+                return Some(rev_path);
+            }
+            let neighbours: Vec<(usize, f64)> = if node == start_id {
+                start_links.clone()
+            } else {
+                self.edges[node].iter()
+                    .cloned()
+                    .chain(finish_links.iter().filter(|link| link.0 == node).map(|link| (finish_id, link.1)))
+                    .collect()
+            };
+            for (next, cost) in neighbours {
+                let next_cost = costs[node] + cost;
+                if next_cost < costs[next] {
+                    costs[next] = next_cost;
+                    parents[next] = Some(node);
+                    queue.push(Queued { priority: next_cost + heuristic(&point_of(next)), node: next, });
+                }
+            }
+        }
+        None
+    }
+
+    fn segments(&self, segs: &mut Vec<(Point, Point)>) {
+        segs.clear();
+        for (node, links) in self.edges.iter().enumerate() {
+            for &(other, _) in links.iter() {
+                if other < node {
+                    segs.push((self.nodes[other], self.nodes[node]));
+                }
+            }
+        }
+    }
+}
+
+// A* queue item, ordered so that `BinaryHeap` pops the lowest priority first
+#[derive(PartialEq)]
+struct Queued {
+    priority: f64,
+    node: usize,
+}
+
+impl Eq for Queued { }
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Queued) -> Ordering {
+        other.priority.partial_cmp(&self.priority).unwrap()
+    }
+}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Queued) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn run_solve_prm(
+    rx: &mpsc::Receiver<MasterPacket>,
+    tx: &mpsc::Sender<SlavePacket>,
+    trans: Trans,
+    roadmap: &mut Option<Roadmap>,
+    debug: bool,
+)
+    -> bool
+{
+    let mut rng = seeded_rng(trans.params.seed);
+    let mut debug_image = DebugImage {
+        tick_id: 0,
+        routes_segs: Vec::new(),
+        sample_seg: SampleTry::None,
+        workers_segs: Vec::new(),
+    };
+    let mut last_ack = 0;
+
+    let reusable = match roadmap {
+        &mut Some(ref roadmap) =>
+            roadmap.fits(&trans),
+        &mut None =>
+            false,
+    };
+    if !reusable {
+        *roadmap = Some(Roadmap::new(&trans));
+    }
+    let roadmap = roadmap.as_mut().unwrap();
+
+    let mut tracker = StatsTracker::new(roadmap.nodes.len());
+    loop {
+        if let Some(path) = roadmap.query(&trans) {
+            send_route(tx, &trans, &mut rng, path, &mut tracker);
+            return false;
+        }
+
+        // no route over the roadmap yet: make it denser and try again
+        for _ in 0 .. PRM_BATCH_NODES {
+            match poll_interrupt(rx, &mut last_ack, &tracker, &trans.params.budget) {
+                None =>
+                    (),
+                Some(Interrupt::Abort) =>
+                    return false,
+                Some(Interrupt::Terminate) =>
+                    return true,
+                Some(Interrupt::OutOfBudget(failure)) => {
+                    send_failure(tx, failure, &mut tracker);
+                    return false;
+                },
+            }
+
+            let added = roadmap.grow(&trans);
+            tracker.sampled(added);
+            tracker.stats.nodes = roadmap.nodes.len();
+            tracker.report(tx);
+
+            if debug {
+                roadmap.segments(&mut debug_image.routes_segs);
+                send_debug_tick(tx, &mut debug_image, last_ack);
+            }
+        }
+    }
+}
+
+// how many closest (in the euclidean sense) nodes are considered when looking for the nearest one by curve length
+const CAR_NEAREST_CANDIDATES: usize = 8;
+
+enum CarCurve {
+    Dubins(DubinsPath),
+    ReedsShepp(ReedsSheppPath),
+}
+
+impl CarCurve {
+    fn length(&self) -> f64 {
+        match self {
+            &CarCurve::Dubins(ref curve) =>
+                curve.length(),
+            &CarCurve::ReedsShepp(ref curve) =>
+                curve.length(),
+        }
+    }
+
+    fn pose_at(&self, distance: f64) -> Pose {
+        match self {
+            &CarCurve::Dubins(ref curve) =>
+                curve.pose_at(distance),
+            &CarCurve::ReedsShepp(ref curve) =>
+                curve.pose_at(distance),
+        }
+    }
+
+    fn poses(&self, length: f64, step: f64) -> Vec<(Pose, Direction)> {
+        match self {
+            &CarCurve::Dubins(ref curve) =>
+                curve.poses(length, step)
+                    .into_iter()
+                    .map(|pose| (pose, Direction::Forward))
+                    .collect(),
+            &CarCurve::ReedsShepp(ref curve) =>
+                curve.poses(length, step),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use std::sync::mpsc;
+
+    use rand::Rng;
+
+    use super::{Trans, run_solve};
+    use super::super::state_space::StateSpace;
+    use super::super::common::{
+        Field, FieldConfig, Point, CircleArea, Obstacle, SolveParams, Planner, Smoothing, Boundary, Kinematics, Budget,
+        SlavePacket,
+    };
+
+    const ARM_BASE: Point = Point { x: 240., y: 170., };
+    const ARM_LINK: f64 = 60.;
+
+    // planar arm of three links hinged at `ARM_BASE`, its state being the joint angles, each relative to the last link
+    struct ArmSpace<'a> {
+        trans: &'a Trans,
+    }
+
+    impl<'a> ArmSpace<'a> {
+        fn joints(&self, angles: &[f64; 3]) -> Vec<Point> {
+            let mut joints = vec![ARM_BASE];
+            let mut heading = 0.;
+            for angle in angles.iter() {
+                heading += angle;
+                let last = joints[joints.len() - 1];
+                joints.push(Point { x: last.x + ARM_LINK * heading.cos(), y: last.y + ARM_LINK * heading.sin(), });
             }
+            joints
+        }
+    }
+
+    impl<'a> StateSpace for ArmSpace<'a> {
+        type State = [f64; 3];
+
+        fn start(&self) -> [f64; 3] {
+            [PI, 0., 0.]
+        }
+
+        fn sample<R>(&self, rng: &mut R) -> [f64; 3] where R: Rng {
+            [rng.gen_range(-PI, PI), rng.gen_range(-PI, PI), rng.gen_range(-PI, PI)]
+        }
+
+        // joint angles scaled by the link length, so that the arm tip moves about as far as the distance says
+        fn distance(&self, src: &[f64; 3], dst: &[f64; 3]) -> f64 {
+            ARM_LINK * src.iter().zip(dst.iter()).map(|(a, b)| (b - a) * (b - a)).sum::<f64>().sqrt()
+        }
+
+        fn interpolate(&self, src: &[f64; 3], dst: &[f64; 3], t: f64) -> [f64; 3] {
+            [src[0] + (dst[0] - src[0]) * t, src[1] + (dst[1] - src[1]) * t, src[2] + (dst[2] - src[2]) * t]
         }
+
+        fn is_goal(&self, state: &[f64; 3]) -> bool {
+            self.trans.goal_reached(&self.position(state))
+        }
+
+        fn position(&self, state: &[f64; 3]) -> Point {
+            self.joints(state)[3]
+        }
+
+        fn is_valid_motion(&self, src: &[f64; 3], dst: &[f64; 3]) -> bool {
+            self.motion(src, dst).iter().all(|&(ref state, _)| {
+                let obstacles = &self.trans.field.obstacles;
+                self.joints(state).windows(2)
+                    .all(|link| !obstacles.iter().any(|obstacle| obstacle.intersects_segment(&link[0], &link[1], 0.)))
+            })
+        }
+    }
+
+    // joint angles do not project onto the field, so the tree is searched by `distance` alone
+    #[test]
+    fn arm_reaches_the_finish() {
+        let field = Field {
+            config: FieldConfig::new(0., 0., 400., 300.),
+            start: Point { x: 40., y: 40., },
+            obstacles: vec![Obstacle::Circle(CircleArea { center: Point { x: 300., y: 215., }, radius: 20., })],
+        };
+        let finish = field.config.finish_area;
+        let params = SolveParams {
+            planner: Planner::Rtt,
+            step: Some(10.),
+            goal_bias: 0.,
+            smoothing: Smoothing::Off,
+            spatial_index: true,
+            seed: 3,
+            robot_radius: 0.,
+            boundary: Boundary::Walled,
+            kinematics: Kinematics::Holonomic,
+            turning_radius: 20.,
+            lazy: false,
+            anytime: false,
+            budget: Budget { max_iterations: Some(200000), max_nodes: None, deadline: None, },
+            workers: 1,
+        };
+        let trans = Trans::new(field, params);
+        let space = ArmSpace { trans: &trans, };
+        assert!(space.nearest_candidates().is_none());
+        let (_master_tx, slave_rx) = mpsc::channel();
+        let (slave_tx, master_rx) = mpsc::channel();
+        assert!(!run_solve(&slave_rx, &slave_tx, &trans, &space, false));
+        let route = master_rx.try_iter()
+            .filter_map(|packet| if let SlavePacket::RouteDone(route) = packet { Some(route) } else { None })
+            .next()
+            .expect("the arm has not reached the finish");
+        let tip = route.path.last().unwrap();
+        assert!(tip.sq_dist(&finish.center) < finish.radius * finish.radius);
     }
 }
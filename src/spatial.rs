@@ -0,0 +1,235 @@
+use super::common::Point;
+
+pub const GRID_CELL_SIZE: f64 = 16.;
+
+pub enum NodesIndex<T> {
+    Linear(Vec<(Point, T)>),
+    Grid(Grid<T>),
+}
+
+impl<T> NodesIndex<T> where T: Clone {
+    pub fn new(field_area: (f64, f64, f64, f64), use_grid: bool) -> NodesIndex<T> {
+        if use_grid {
+            NodesIndex::Grid(Grid::new(field_area, GRID_CELL_SIZE))
+        } else {
+            NodesIndex::Linear(Vec::new())
+        }
+    }
+
+    pub fn insert(&mut self, point: Point, item: T) {
+        match self {
+            &mut NodesIndex::Linear(ref mut items) =>
+                items.push((point, item)),
+            &mut NodesIndex::Grid(ref mut grid) =>
+                grid.insert(point, item),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        match self {
+            &mut NodesIndex::Linear(ref mut items) =>
+                items.clear(),
+            &mut NodesIndex::Grid(ref mut grid) =>
+                grid.clear(),
+        }
+    }
+
+    pub fn nearest(&self, point: &Point) -> Option<T> {
+        self.k_nearest(point, 1).pop()
+    }
+
+    pub fn k_nearest(&self, point: &Point, k: usize) -> Vec<T> {
+        self.k_nearest_kept(point, k, |_| true)
+    }
+
+    // like `k_nearest` but skipping the items `keep` rejects, e.g. the ones removed since the index was built
+    pub fn k_nearest_kept<F>(&self, point: &Point, k: usize, keep: F) -> Vec<T> where F: Fn(&T) -> bool {
+        let by_distance = |a: &(T, f64), b: &(T, f64)| a.1.partial_cmp(&b.1).unwrap();
+        let mut found = match self {
+            &NodesIndex::Linear(ref items) => {
+                let mut found: Vec<_> = items.iter()
+                    .filter(|&&(_, ref item)| keep(item))
+                    .map(|&(ref p, ref item)| (item.clone(), p.sq_dist(point)))
+                    .collect();
+                // only the k closest ones get sorted below
+                if k > 0 && k < found.len() {
+                    found.select_nth_unstable_by(k - 1, by_distance);
+                }
+                found
+            },
+            &NodesIndex::Grid(ref grid) =>
+                grid.k_nearest(point, k, &keep),
+        };
+        found.truncate(k);
+        found.sort_by(by_distance);
+        found.into_iter().map(|(item, _)| item).collect()
+    }
+
+    pub fn within(&self, point: &Point, radius: f64) -> Vec<T> {
+        match self {
+            &NodesIndex::Linear(ref items) =>
+                items.iter()
+                    .filter(|&&(ref p, _)| p.sq_dist(point) <= radius * radius)
+                    .map(|&(_, ref item)| item.clone())
+                    .collect(),
+            &NodesIndex::Grid(ref grid) =>
+                grid.within(point, radius),
+        }
+    }
+}
+
+pub struct Grid<T> {
+    min_x: f64,
+    min_y: f64,
+    cell_size: f64,
+    cols: usize,
+    rows: usize,
+    cells: Vec<Vec<(Point, T)>>,
+}
+
+impl<T> Grid<T> where T: Clone {
+    pub fn new(field_area: (f64, f64, f64, f64), cell_size: f64) -> Grid<T> {
+        let (min_x, min_y, max_x, max_y) = field_area;
+        let cols = ((max_x - min_x) / cell_size).ceil().max(1.) as usize;
+        let rows = ((max_y - min_y) / cell_size).ceil().max(1.) as usize;
+        Grid {
+            min_x, min_y, cell_size, cols, rows,
+            cells: (0 .. cols * rows).map(|_| Vec::new()).collect(),
+        }
+    }
+
+    fn cell_of(&self, point: &Point) -> (usize, usize) {
+        let clamp = |value: f64, limit: usize| {
+            if value < 0. {
+                0
+            } else if value as usize >= limit {
+                limit - 1
+            } else {
+                value as usize
+            }
+        };
+        (
+            clamp((point.x - self.min_x) / self.cell_size, self.cols),
+            clamp((point.y - self.min_y) / self.cell_size, self.rows),
+        )
+    }
+
+    pub fn insert(&mut self, point: Point, item: T) {
+        let (col, row) = self.cell_of(&point);
+        self.cells[row * self.cols + col].push((point, item));
+    }
+
+    pub fn clear(&mut self) {
+        for cell in self.cells.iter_mut() {
+            cell.clear();
+        }
+    }
+
+    fn visit_cell<F>(&self, col: isize, row: isize, point: &Point, keep: &F, found: &mut Vec<(T, f64)>) where F: Fn(&T) -> bool {
+        if col < 0 || row < 0 || col as usize >= self.cols || row as usize >= self.rows {
+            return;
+        }
+        for &(ref p, ref item) in self.cells[row as usize * self.cols + col as usize].iter() {
+            if keep(item) {
+                found.push((item.clone(), p.sq_dist(point)));
+            }
+        }
+    }
+
+    // scans square rings of cells around the point until the rest of the grid cannot contain anything closer
+    pub fn k_nearest<F>(&self, point: &Point, k: usize, keep: &F) -> Vec<(T, f64)> where F: Fn(&T) -> bool {
+        if k == 0 {
+            return Vec::new();
+        }
+        let (col, row) = self.cell_of(point);
+        let (col, row) = (col as isize, row as isize);
+        let max_ring = if self.cols > self.rows { self.cols } else { self.rows } as isize;
+        let mut found = Vec::new();
+        for ring in 0 .. max_ring {
+            if ring == 0 {
+                self.visit_cell(col, row, point, keep, &mut found);
+            } else {
+                for x in col - ring ..= col + ring {
+                    self.visit_cell(x, row - ring, point, keep, &mut found);
+                    self.visit_cell(x, row + ring, point, keep, &mut found);
+                }
+                for y in row - ring + 1 .. row + ring {
+                    self.visit_cell(col - ring, y, point, keep, &mut found);
+                    self.visit_cell(col + ring, y, point, keep, &mut found);
+                }
+            }
+            if found.len() >= k {
+                found.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+                found.truncate(k);
+                let bound = ring as f64 * self.cell_size;
+                if found[k - 1].1 <= bound * bound {
+                    break;
+                }
+            }
+        }
+        found
+    }
+
+    pub fn within(&self, point: &Point, radius: f64) -> Vec<T> {
+        let (col_a, row_a) = self.cell_of(&Point { x: point.x - radius, y: point.y - radius, });
+        let (col_b, row_b) = self.cell_of(&Point { x: point.x + radius, y: point.y + radius, });
+        let mut found = Vec::new();
+        for row in row_a ..= row_b {
+            for col in col_a ..= col_b {
+                for &(ref p, ref item) in self.cells[row * self.cols + col].iter() {
+                    if p.sq_dist(point) <= radius * radius {
+                        found.push(item.clone());
+                    }
+                }
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::NodesIndex;
+    use super::super::common::{Point, seeded_rng};
+
+    const FIELD_AREA: (f64, f64, f64, f64) = (0., 112., 640., 480.);
+
+    // the linear scan is kept around as the reference the grid has to agree with
+    #[test]
+    fn grid_agrees_with_linear_scan() {
+        let mut rng = seeded_rng(17);
+        let random_point = |rng: &mut ::rand::ChaChaRng| {
+            let (min_x, min_y, max_x, max_y) = FIELD_AREA;
+            Point { x: rng.gen_range(min_x, max_x), y: rng.gen_range(min_y, max_y), }
+        };
+        for &count in [0, 1, 5, 100, 2000].iter() {
+            let mut linear = NodesIndex::new(FIELD_AREA, false);
+            let mut grid = NodesIndex::new(FIELD_AREA, true);
+            let mut points = Vec::new();
+            for item in 0 .. count {
+                let point = random_point(&mut rng);
+                linear.insert(point, item);
+                grid.insert(point, item);
+                points.push(point);
+            }
+            for _ in 0 .. 200 {
+                let query = random_point(&mut rng);
+                let k = rng.gen_range(0, 12);
+                // ties may come in any order, so distances are compared rather than items
+                let distances = |found: Vec<usize>| -> Vec<f64> {
+                    found.into_iter().map(|item| points[item].sq_dist(&query)).collect()
+                };
+                assert_eq!(distances(linear.k_nearest(&query, k)), distances(grid.k_nearest(&query, k)));
+
+                let radius = rng.gen_range(0., 120.);
+                let mut linear_within = linear.within(&query, radius);
+                let mut grid_within = grid.within(&query, radius);
+                linear_within.sort();
+                grid_within.sort();
+                assert_eq!(linear_within, grid_within);
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use rand::Rng;
+
+use super::common::{Point, Direction};
+
+// motions are checked for collisions as chains of states no further apart than this
+pub const MOTION_RESOLUTION: f64 = 2.;
+
+// configuration space the plain (and lazy) RTT tree grows in: plain points for a holonomic robot, poses for a car or
+// any other states with a distance and motions between them, like joint angles of an arm
+pub trait StateSpace {
+    type State: Clone;
+
+    // state the tree is rooted at
+    fn start(&self) -> Self::State;
+
+    fn sample<R>(&self, rng: &mut R) -> Self::State where R: Rng;
+
+    // length of the shortest motion between the states
+    fn distance(&self, src: &Self::State, dst: &Self::State) -> f64;
+
+    // state at the `t` fraction (from 0 to 1) of the shortest motion between the states
+    fn interpolate(&self, src: &Self::State, dst: &Self::State, t: f64) -> Self::State;
+
+    // the robot has arrived at the finish
+    fn is_goal(&self, state: &Self::State) -> bool;
+
+    // projection onto the field the tree and the route are drawn with
+    fn position(&self, state: &Self::State) -> Point;
+
+    // when states close by `distance` are close by `position` too, the nearest node is looked for by `distance` only
+    // among this many closest nodes in the planar spatial index, otherwise (`None`) among all of them
+    fn nearest_candidates(&self) -> Option<usize> {
+        None
+    }
+
+    // moves from `src` towards `sample` no further than `step`, `None` when there is nowhere to move
+    fn steer(&self, src: &Self::State, sample: &Self::State, step: Option<f64>) -> Option<Self::State> {
+        let distance = self.distance(src, sample);
+        if distance <= 0. {
+            return None;
+        }
+        match step {
+            Some(step) if step < distance =>
+                Some(self.interpolate(src, sample, step / distance)),
+            _ =>
+                Some(sample.clone()),
+        }
+    }
+
+    // states along the motion from `src` (not included) to `dst` with the direction the robot arrives to each of them
+    fn motion(&self, src: &Self::State, dst: &Self::State) -> Vec<(Self::State, Direction)> {
+        let count = (self.distance(src, dst) / MOTION_RESOLUTION).ceil().max(1.) as usize;
+        (1 ..= count)
+            .map(|i| (self.interpolate(src, dst, i as f64 / count as f64), Direction::Forward))
+            .collect()
+    }
+
+    // the robot can follow the motion without hitting anything
+    fn is_valid_motion(&self, src: &Self::State, dst: &Self::State) -> bool;
+}